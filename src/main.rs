@@ -10,7 +10,19 @@ use ndarray_linalg::Eig;
 use std::time::Instant;
 use rand::prelude::*;
 use ndarray_rand::rand_distr::num_traits::real::Real;
-use crate::models::conjectures::conjectures_wagner_1::State;
+use crate::models::conjectures::conjectures_wagner_1::{State, Connectivity};
+use crate::models::moveGenerator::GraphMode;
+use crate::models::spectral::GraphMatrix;
+use crate::tools::calc::Sampler;
+use crate::methods::playout::Playout;
+use crate::models::policyEncoding::PolicyEncoding;
+use crate::methods::CMAES::Restart;
+use crate::methods::GA::Crossover;
+use crate::methods::BFS::Duplicates;
+use crate::methods::ILS::Kick;
+use crate::methods::LS::Cooling;
+use crate::methods::GRAVE::{Selection, Backup};
+use crate::models::conjectures::objective::{Objective, Aggregation, Normalization, Degenerate};
 
 mod tools;
 mod methods;
@@ -25,6 +37,32 @@ fn main() {
     let heuristic = 10.0;
     let timeout = 60.0;
     let verbose = true;
+    let mode = GraphMode::AnyGraph;
+    let edits = 0;
+    let connectivity = Connectivity::Ignore;
+    let spectral = GraphMatrix::Laplacian;
+    let objective: Option<Objective> = None;
+    let aggregation: Option<Aggregation> = None;
+    let tolerance: Option<f64> = None;
+    let normalization = Normalization::Raw;
+    let degenerate = Degenerate::Fallback;
+    let sampler = Sampler::new();
+    let playout = Playout::HeuristicSoftmax;
+    let consider_non_term = true;
+    let policy_encoding = PolicyEncoding::MoveIdentity;
+    let policy_file = "";
+    let table_file = "";
+    let selection = Selection::Grave;
+    let backup = Backup::Mean;
+    let cmaes_restart = Restart::Bipop;
+    let bfs_max_open = 100000;
+    let duplicates = Duplicates::Labelled;
+    let kick = Kick::Edges(2);
+    let ils_max_n = terminal + 4;
+    let crossover = Crossover::Mixed;
+    let cooling = Cooling::Exponential(0.001);
+    let checkpoint_every = 60.0;
+    let resume = std::env::args().any(|arg| arg == "--resume");
 
     let total = Instant::now();
 
@@ -32,20 +70,42 @@ fn main() {
         let mut st = State::new();
         st.size_terminal = terminal;
         st.conj = i as usize;
+        st.mode = mode;
+        st.max_edits = edits;
+        st.connectivity = connectivity;
+        st.spectral = spectral;
+        st.objective = objective;
+        st.aggregation = aggregation;
+        st.tolerance = tolerance;
+        st.normalization = normalization;
+        st.degenerate = degenerate;
+        st.sampler = sampler;
+        st.playout = playout;
+        st.consider_non_term = consider_non_term;
+        st.policy_encoding = policy_encoding;
+        st.checkpoint_every = checkpoint_every;
+        st.resume = resume;
 
         let start = Instant::now();
-        println!("Conjecture {}", i);
+        println!("Conjecture {} ({} mode, {})", st.conjecture_name(), mode.generator().name(), st.non_term_report());
 
         let st1 = methods::NMCS::launch_nmcs(st.clone(), level, heuristic, verbose, timeout, String::from(format!("NMCS{}", level)));
-        let st2 = methods::NRPA::launch_nrpa(level, st.clone(), timeout, verbose, String::from(format!("NRPA{}", level)));
-        let st3 = methods::GRAVE::launch_grave(st.clone(), 50, 0.0, heuristic, timeout, verbose, "GRAVE".to_string());
-        let st4 = methods::BFS::launch_bfs(st.clone(),heuristic, -1, timeout, verbose, "BFS".to_string());
-        let st5 = methods::ILS::iterative_local_search(terminal, 4, i as usize, timeout, verbose, "ILS".to_string());
-        let st6 = methods::CMAES::launch_CMAES(10, 2000, i as usize, terminal, verbose, "CMAES".to_string());
+        let st2 = methods::NRPA::launch_nrpa(level, st.clone(), policy_file, timeout, verbose, String::from(format!("NRPA{}", level)));
+        let st3 = methods::GRAVE::launch_grave(st.clone(), 50, 0.0, heuristic, selection, backup, table_file, timeout, verbose, "GRAVE".to_string());
+        let st4 = methods::BFS::launch_bfs(st.clone(),heuristic, -1, bfs_max_open, duplicates, timeout, verbose, "BFS".to_string());
+        let st5 = methods::ILS::iterative_local_search(st.clone(), terminal, ils_max_n, 4, kick, timeout, verbose, "ILS".to_string());
+        let st6 = methods::CMAES::launch_CMAES(st.clone(), cmaes_restart, 0.3, timeout, verbose, "CMAES".to_string());
+        let st7 = methods::NMCS::launch_beam_nmcs(st.clone(), level, 3, heuristic, verbose, timeout, String::from(format!("BeamNMCS{}", level)));
+        let st8 = methods::NMCS::launch_lazy_nmcs(st.clone(), level + 1, 4, 0.75, heuristic, verbose, timeout, String::from(format!("LazyNMCS{}", level + 1)));
+        let st9 = methods::NRPA::launch_gnrpa(level, st.clone(), heuristic, 1.0, policy_file, timeout, verbose, String::from(format!("GNRPA{}", level)));
+        let st10 = methods::CMAES::launch_gaussian_EDA(st.clone(), 10, 2000, terminal, verbose, "EDA".to_string());
+        let st11 = methods::GA::launch_ga(st.clone(), 50, 3, 0.2, crossover, kick, timeout, verbose, "GA".to_string());
+        let st12 = methods::LS::launch_annealing(st.clone(), cooling, 1.0, 10000, timeout, verbose, "SA".to_string());
+        let st13 = methods::LS::launch_tabu(st.clone(), 20, timeout, verbose, "Tabu".to_string());
 
         let end = Instant::now();
         let duration = end.duration_since(start);
-        println!("Conjecture {}\nTime : {}s, {}min", i, duration.as_secs_f64(), duration.as_secs_f64() / 60.0);
+        println!("Conjecture {}\nTime : {}s, {}min", st.conjecture_name(), duration.as_secs_f64(), duration.as_secs_f64() / 60.0);
     }
 
     let end = Instant::now();
@@ -1,11 +1,52 @@
 use crate::models::conjectures::conjectures_wagner_1::{State};
-use crate::tools::calc::softmaxChoice;
+use crate::methods::playout::search_playout;
 use std::time::Instant;
-use crate::tools::resultSaver::writeLine;
-use std::collections::HashMap;
-use crate::tools::{graphToDot, saveMatrix};
+use std::cmp::Ordering;
+use crate::tools::resultSaver::{recordBest, writeLine};
+use std::collections::{BinaryHeap, HashMap};
+use crate::tools::checkpoint::{Checkpoint, checkpointName, checkpointDue, saveCheckpoint, resumeCheckpoint, removeCheckpoint};
+
+// which generated graphs are dropped as already met: None keeps them all, Labelled drops a graph with the same adjacency matrix,
+// Isomorphic drops a graph isomorphic to one already met
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Duplicates {
+    None,
+    Labelled,
+    Isomorphic
+}
+
+impl Duplicates {
+    // true if st was met already, otherwise it is added to seen, which keeps the edge_bits of the graphs by key:
+    // Labelled compares them, Isomorphic confirms a shared isomorphism_key with an exact test since non-isomorphic graphs
+    // (e.g. regular ones) may share it
+    fn met(&self, st: &State, seen: &mut HashMap<u64, Vec<Vec<u64>>>) -> bool {
+        let key = match self {
+            Duplicates::None => return false,
+            Duplicates::Labelled => st.adjacency_key(),
+            Duplicates::Isomorphic => st.isomorphism_key()
+        };
+        let bits = st.edge_bits();
+        let graphs = seen.entry(key).or_default();
+        let same = |g: &Vec<u64>| match self {
+            Duplicates::Isomorphic => st.isomorphic(&st.with_edge_bits(g)),
+            _ => *g == bits
+        };
+        if graphs.iter().any(same) {
+            return true
+        }
+        graphs.push(bits);
+        false
+    }
 
-static SKIP_REPEATING_SCORES : bool = false;
+    // seen holding only the open nodes
+    fn remember(&self, open_nodes: &BinaryHeap<WS>) -> HashMap<u64, Vec<Vec<u64>>> {
+        let mut seen = HashMap::new();
+        for node in open_nodes.iter() {
+            self.met(&node.s, &mut seen);
+        }
+        seen
+    }
+}
 
 #[derive(Clone)]
 pub struct WS{ // Weight-State
@@ -13,115 +54,108 @@ pub struct WS{ // Weight-State
     pub s: State
 }
 
-
-pub fn insertDicho(l : &Vec<WS>, node : &WS) -> usize{
-    let mut i = l.len()/2;
-    let mut mi = 0;
-    let mut ma = l.len()-1;
-
-    while (i != 0 && l[i-1].w > node.w) || l[i].w < node.w {
-        if l[i].w == node.w {
-            return i;
-        }
-        if l[i].w < node.w {
-            mi = i + 1;
-            if mi > ma{
-                return mi;
-            }
-        } else {
-            ma = i;
-        }
-        i = (mi as f64/2.0 + (ma as f64)/2.0) as usize
+impl PartialEq for WS {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
     }
-
-    return i;
 }
 
-pub fn playout(mut st: State, heuristic_w : f64) -> State {
-    let mut best_state: State = st.clone();
-    let mut best_state_score = best_state.score();
-
-    while !st.terminal() {
-        let moves = st.legal_moves();
-        if moves.len() == 0 {
-            return st
-        }
+impl Eq for WS {}
 
-        let mut i = ((moves.len() as f64)*rand::random::<f64>()) as usize;
-        if heuristic_w != 0.0 {
-            let mut weights = Vec::new();
-            for &m in &moves{
-                weights.push(heuristic_w*st.heuristic(m));
-            }
-            i = softmaxChoice(weights);
-        }
-
-        let mv = moves[i];
-        st.play(mv);
-
-        if State::CONSIDER_NON_TERM {
-            let sc = st.score();
-            if sc > best_state_score {
-                best_state_score = sc;
-                best_state = st.clone();
-                best_state.best_score = sc;
-            }
-        }
+impl PartialOrd for WS {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    if State::CONSIDER_NON_TERM {
-        return best_state
+// the open list is a max-heap on w
+impl Ord for WS {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.w.total_cmp(&other.w)
     }
+}
 
-    return st
+#[derive(Default, Debug)]
+pub struct BFSStats {
+    pub expanded: usize,
+    pub generated: usize,
+    pub duplicates: usize,
+    pub pruned: usize,
+    pub max_open: usize
 }
 
-pub fn BFS(inist: State, heuristic_w: f64, p:i32, timeout: f64, verbose: bool, registerName: String) -> State {
-    if p < 0 {
-        //println!("attention, utilisation des scores des état non finaux au lieu des scores de playouts pour déterminer la valeur d'un noeud")
+impl BFSStats {
+    pub fn report(&self) -> String {
+        format!("{} nodes expanded, {} generated, {} duplicates dropped, {} pruned, at most {} open",
+                self.expanded, self.generated, self.duplicates, self.pruned, self.max_open)
     }
+}
+
+// keeps the max_open best nodes of the open list
+fn prune(open_nodes: BinaryHeap<WS>, max_open: usize, stats: &mut BFSStats) -> BinaryHeap<WS> {
+    let mut nodes = open_nodes.into_sorted_vec();
+    let removed = nodes.len().saturating_sub(max_open);
+    nodes.drain(..removed);
+    stats.pruned += removed;
+    BinaryHeap::from(nodes)
+}
 
-    let mut st = inist.clone();
+// best-first search, a node is valued by the best of p+1 playouts from it or by its own score when p < 0;
+// max_open > 0 bounds the open list (the worst nodes are dropped once it is a quarter over) and the graphs remembered as met
+#[allow(clippy::too_many_arguments)]
+pub fn BFS(inist: State, heuristic_w: f64, p:i32, max_open: usize, duplicates: Duplicates, timeout: f64, verbose: bool, registerName: String) -> State {
+    let st = inist.clone();
     let mut start_time = Instant::now();
 
-    let mut open_nodes = Vec::new();
-    open_nodes.push(WS{w : 0.0, s : st.clone()});
+    let mut open_nodes = BinaryHeap::from(vec![WS{w : 0.0, s : st.clone()}]);
 
     let mut best_score_yet = f64::NEG_INFINITY;
     let mut best_state_yet = st.clone();
-    let mut opened_nodes = 0;
-
-    let mut visitedScores: HashMap<i64, bool> = HashMap::new();
-
-    while open_nodes.len() != 0 {
-        //println!("open nodes : {}", open_nodes.len());
-
-        opened_nodes += 1;
-        if start_time.elapsed().as_secs_f64() > timeout && timeout > 0.0 {
-            return best_state_yet
+    let mut stats = BFSStats::default();
+
+    // the open list is the pool of the checkpoint
+    let name = checkpointName(&registerName, &inist);
+    let mut last_checkpoint = Instant::now();
+    if let Some(cp) = resumeCheckpoint(&name, &inist) {
+        start_time = cp.restore();
+        best_score_yet = cp.best_score;
+        best_state_yet = cp.best;
+        stats.expanded = cp.step;
+        open_nodes = cp.pool.into_iter().map(|(w, s)| WS{w, s}).collect();
+    }
+    // a resumed run only remembers its open list, the graphs expanded before the checkpoint may be generated again
+    let mut seen = duplicates.remember(&open_nodes);
+
+    let method = if p >= 0 { "BFS with playout" } else { "BFS" };
+    'search: while let Some(node) = open_nodes.pop() {
+        if checkpointDue(&inist, &mut last_checkpoint) {
+            let mut cp = Checkpoint::new(&start_time, best_score_yet, &best_state_yet, &inist);
+            cp.step = stats.expanded;
+            cp.pool = open_nodes.iter().chain(std::iter::once(&node)).map(|ws| (ws.w, ws.s.clone())).collect();
+            saveCheckpoint(&name, &cp);
         }
 
-        let mut node = open_nodes.pop().unwrap();
-        //println!("node opened with score : {}", node.w);
-        //println!("{}", node.s.adj_mat);
-
-        if SKIP_REPEATING_SCORES {
-            while visitedScores.contains_key(&((node.w*10000000000.0) as i64)) && node.w != 0.0 && open_nodes.len() != 0 {
-                node = open_nodes.pop().unwrap();
-            }
-            visitedScores.insert(((node.w*10000000000.0) as i64), true);
+        if start_time.elapsed().as_secs_f64() > timeout && timeout > 0.0 {
+            break
         }
+        stats.expanded += 1;
 
         for m in node.s.legal_moves() {
             let mut new_state = node.s.clone();
             new_state.play(m);
 
-            if p >= 0 {
-                let mut best_playout_state = playout(new_state.clone(), heuristic_w);
+            if duplicates.met(&new_state, &mut seen) {
+                stats.duplicates += 1;
+                continue
+            }
+            stats.generated += 1;
+
+            let w = if p >= 0 {
+                let mut best_playout_state = search_playout(new_state.clone(), heuristic_w, false);
                 let mut best_playout_state_score = best_playout_state.score();
 
                 for _ in 0..p {
-                    let mut playout_state = playout(new_state.clone(), heuristic_w);
+                    let playout_state = search_playout(new_state.clone(), heuristic_w, false);
                     let playout_state_score = playout_state.score();
 
                     if playout_state_score > best_playout_state_score {
@@ -133,97 +167,85 @@ pub fn BFS(inist: State, heuristic_w: f64, p:i32, timeout: f64, verbose: bool, r
                 if best_playout_state_score > best_score_yet {
                     best_score_yet = best_playout_state_score;
                     best_state_yet = best_playout_state.clone();
-
-                    println!("BFS best score yet : {} after {}", best_score_yet, start_time.elapsed().as_secs_f64());
-                    if verbose {
-                        let new_name = registerName.clone() + &*"_evolution".to_string();
-                        writeLine("Conjecture ".to_owned() + &*best_state_yet.conj.to_string()
-                                      + " | BFS best score yet : " + &*best_score_yet.to_string()
-                                      + " after " + &*start_time.elapsed().as_secs_f64().to_string()
-                                      + "s, " + &*best_state_yet.n_sommet.to_string()
-                                      + " vertices\n", new_name);
-                                      }
-
-                    if best_playout_state_score > 0.0001 {
-                        let elapsed = start_time.elapsed().as_secs_f64();
-                        if verbose {
-                            writeLine("Conjecture ".to_owned() + &*inist.conj.to_string()
-                                          + "\n        Counterexample found in " + &*elapsed.to_string()
-                                          + "s: best score = " + &*best_playout_state_score.to_string()
-                                          + "\n        With BFS with playout"
-                                          + ", " + &*best_state_yet.n_sommet.to_string()
-                                          + " vertices\n\n", registerName.clone());
-                                          }
-                        println!("Conjecture {}\n   Counter-example found with BFS after {}s\n\n", inist.conj, elapsed);
-
-                        graphToDot::adj_matrix_to_dot(best_state_yet.adj_mat.clone(), &*format!("{}/conj{}", registerName, best_state_yet.conj));
-                        saveMatrix::save_matrix(&*format!("{}/conj{}", registerName, best_state_yet.conj), best_state_yet.adj_mat.clone());
-
-                        return best_state_yet
+                    if recordBest(&best_state_yet, best_score_yet, &start_time, method, &format!(" ({})", stats.report()), verbose, &registerName) {
+                        break 'search
                     }
                 }
-
-                let new_ws = WS{w : best_playout_state_score, s : new_state};
-                let mut i = 0;
-                if open_nodes.len() != 0 {
-                    i = insertDicho(&open_nodes, &new_ws);
-                }
-
-                open_nodes.insert(i, new_ws);
-
+                best_playout_state_score
             } else {
                 let sc = new_state.score();
                 if sc > best_score_yet {
                     best_score_yet = sc;
                     best_state_yet = new_state.clone();
-
-                    println!("BFS best score yet : {} after {}", best_score_yet, start_time.elapsed().as_secs_f64());
-                    if verbose {
-                        let new_name = registerName.clone() + &*"_evolution".to_string();
-                        writeLine("Conjecture ".to_owned() + &*best_state_yet.conj.to_string()
-                                      + " | BFS best score yet : " + &*best_score_yet.to_string()
-                                      + " after " + &*start_time.elapsed().as_secs_f64().to_string()
-                                      + "s, " + &*best_state_yet.n_sommet.to_string()
-                                      + " vertices\n", new_name);
-                                      }
-
-                    if sc > 0.0001 {
-                        let elapsed = start_time.elapsed().as_secs_f64();
-                        if verbose {
-                            writeLine("Conjecture ".to_owned() + &*inist.conj.to_string()
-                                          + "\n        Counterexample found in " + &*elapsed.to_string()
-                                          + "s: best score = " + &*sc.to_string()
-                                          + "\n        With BFS"
-                                          + ", " + &*best_state_yet.n_sommet.to_string()
-                                          + " vertices\n\n", registerName.clone());
-                                          }
-                        println!("Conjecture {}\n   Counter-example found with BFS after {}s\n\n", inist.conj, elapsed);
-
-                        graphToDot::adj_matrix_to_dot(best_state_yet.adj_mat.clone(), &*format!("{}/conj{}", registerName, best_state_yet.conj));
-                        saveMatrix::save_matrix(&*format!("{}/conj{}", registerName, best_state_yet.conj), best_state_yet.adj_mat.clone());
-
-                        return best_state_yet
+                    if recordBest(&best_state_yet, best_score_yet, &start_time, method, &format!(" ({})", stats.report()), verbose, &registerName) {
+                        break 'search
                     }
                 }
-                if !new_state.terminal() {
-                    //println!("{}", new_state.n_sommet);
-                    //println!("{}, {}", m.from, m.to);
-                    let new_ws = WS{w: sc, s: new_state};
-
-                    let mut i = 0;
-                    if open_nodes.len() != 0 {
-                        i = insertDicho(&open_nodes, &new_ws);
-                    }
-                    open_nodes.insert(i, new_ws);
+                // terminal states are not expanded
+                if new_state.terminal() {
+                    continue
                 }
-            }
+                sc
+            };
+
+            open_nodes.push(WS{w, s: new_state});
+        }
+
+        stats.max_open = stats.max_open.max(open_nodes.len());
+        if max_open > 0 && open_nodes.len() > max_open + max_open/4 {
+            open_nodes = prune(open_nodes, max_open, &mut stats);
+            // seen is bounded with the open list: the expanded and pruned graphs are forgotten and may be generated again
+            seen = duplicates.remember(&open_nodes);
         }
     }
 
-    //println!(" exhausted all nodes : {}", opened_nodes);
-    return best_state_yet
+    println!("BFS {} : {}", inist.conjecture_name(), stats.report());
+    if verbose {
+        let new_name = registerName.clone() + &*"_evolution".to_string();
+        let _ = writeLine("Conjecture ".to_owned() + &*inist.conjecture_name()
+                      + " | BFS " + &*stats.report() + "\n", new_name);
+    }
+
+    removeCheckpoint(&name);
+    best_state_yet
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn launch_bfs(init_stat: State, heuristic_w: f64, p:i32, max_open: usize, duplicates: Duplicates, timeout: f64, verbose: bool, registerName: String) -> State {
+    return BFS(init_stat, heuristic_w, p, max_open, duplicates, timeout, verbose, registerName);
 }
 
-pub fn launch_bfs(init_stat: State, heuristic_w: f64, p:i32, timeout: f64, verbose: bool, registerName: String) -> State {
-    return BFS(init_stat, heuristic_w, p, timeout, verbose, registerName);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(n: usize, edges: &[(usize, usize)]) -> State {
+        let mut st = State::new().empty_like(n);
+        for &(i, j) in edges {
+            st.add_arete(i, j as i32);
+        }
+        st
+    }
+
+    #[test]
+    fn duplicates_are_exact() {
+        let path = graph(4, &[(0, 1), (1, 2), (2, 3)]);
+        let relabelled = graph(4, &[(1, 0), (0, 3), (3, 2)]);
+        // K3,3 and the prism share the Weisfeiler-Lehman hash
+        let k33 = graph(6, &[(0, 3), (0, 4), (0, 5), (1, 3), (1, 4), (1, 5), (2, 3), (2, 4), (2, 5)]);
+        let prism = graph(6, &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (0, 3), (1, 4), (2, 5)]);
+
+        let mut seen = HashMap::new();
+        assert!(!Duplicates::Labelled.met(&path, &mut seen));
+        assert!(Duplicates::Labelled.met(&path, &mut seen));
+        assert!(!Duplicates::Labelled.met(&relabelled, &mut seen));
+
+        let mut seen = HashMap::new();
+        for st in [&path, &k33, &prism] {
+            assert!(!Duplicates::Isomorphic.met(st, &mut seen));
+        }
+        assert!(Duplicates::Isomorphic.met(&relabelled, &mut seen));
+        assert!(Duplicates::Isomorphic.met(&prism, &mut seen));
+        assert!(!Duplicates::None.met(&path, &mut seen));
+    }
 }
@@ -1,37 +1,17 @@
 use std::time::Instant;
-use nalgebra::{DMatrix, DVector, Dynamic, OMatrix, OVector, SVD};
+use nalgebra::{DMatrix, DVector, Dynamic, OMatrix, OVector, SVD, SymmetricEigen};
 use rand::distributions::Distribution;
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
 use rand_distr::StandardNormal;
 use stat::covariance;
 use crate::models::conjectures::conjectures_wagner_1::State;
-use crate::tools::{graphToDot, saveMatrix};
-use crate::tools::resultSaver::writeLine;
-
-pub fn encode(adj_mat: &DMatrix<f64>) -> Vec<f64> {
-    let mut new_matrix = adj_mat.clone();
-    for i in 0..adj_mat.nrows(){
-        for j in 0.. adj_mat.nrows(){
-            if new_matrix[(i, j)] == -1.0 {
-                new_matrix[(i, j)] = 0.0;
-            }
-        }
-    }
-
-    let encoded_vec: Vec<f64> = new_matrix.as_slice().to_vec();
-
-    encoded_vec
-}
-
-pub fn assemble_encodings(states: Vec<State>) -> Vec<Vec<f64>> {
-    let mut ensemble: Vec<Vec<f64>> = vec![];
-    for st in &states {
-        let encoding = encode(&st.adj_mat);
-        ensemble.push(encoding);
-    }
+use crate::models::genome::Genome;
+use crate::tools::resultSaver::recordBest;
+use crate::tools::calc::{randomF64, withRng};
+use crate::tools::checkpoint::{Checkpoint, checkpointName, checkpointDue, saveCheckpoint, resumeCheckpoint, removeCheckpoint};
 
-    ensemble
+pub fn assemble_encodings(states: &[State], genome: &Genome) -> Vec<Vec<f64>> {
+    states.iter().map(|st| genome.encode(st)).collect()
 }
 
 pub fn ensemble_to_DMatrix(ensemble: &Vec<Vec<f64>>) -> DMatrix<f64> {
@@ -56,7 +36,7 @@ pub fn gaussian_mean(matrix: &DMatrix<f64>) -> Vec<f64> {
         mean.push(sum);
     }
 
-    let denom = matrix.ncols() as f64;
+    let denom = matrix.nrows() as f64;
     mean = mean.iter().map(|x| *x/denom).collect();
 
     mean
@@ -95,10 +75,8 @@ pub fn svd(cov_mat: DMatrix<f64>) -> (OMatrix<f64, Dynamic, Dynamic>, OVector<f6
     (P, delta)
 }
 
-pub fn construct_state(child: Vec<(&f64, &(usize, usize))>, size: usize) -> State {
-    let mut new_state = State::new();
-    new_state.n_sommet = size;
-    new_state.adj_mat.resize_mut(size, size, 0.0);
+pub fn construct_state(child: Vec<(&f64, &(usize, usize))>, size: usize, init_st: &State) -> State {
+    let mut new_state = init_st.empty_like(size);
     for &child in &child {
         let i = child.1.0;
         let j = child.1.1;
@@ -109,18 +87,18 @@ pub fn construct_state(child: Vec<(&f64, &(usize, usize))>, size: usize) -> Stat
             new_state.n_arete += 1;
         }
     }
+    new_state.repair();
 
     new_state
 }
 
-pub fn create_child(P: &OMatrix<f64, Dynamic, Dynamic>, delta: &OVector<f64, Dynamic>, mean: &Vec<f64>) -> State {
-    let mut rng = thread_rng();
+pub fn create_child(P: &OMatrix<f64, Dynamic, Dynamic>, delta: &OVector<f64, Dynamic>, mean: &Vec<f64>, genome: &Genome, init_st: &State) -> State {
     let dist = StandardNormal;
 
     let mut child = vec![];
     let size = delta.len();
     for i in 0..size {
-        let mut ele: f64 = dist.sample(&mut rng);
+        let mut ele: f64 = withRng(|rng| dist.sample(rng));
         ele = ele*(delta[i].sqrt());
         child.push(ele);
     }
@@ -128,6 +106,7 @@ pub fn create_child(P: &OMatrix<f64, Dynamic, Dynamic>, delta: &OVector<f64, Dyn
     let coef = 1.0;
     let child: Vec<_> = (coef*P*child).as_slice().to_vec().iter().zip(mean.iter()).map(|(&a, &b)| a + b).collect();
 
+    // each edge is sampled once, present when above its mean
     let mut binary_child: Vec<f64> = vec![];
     for k in 0..child.len() {
         if child[k] > mean[k] {
@@ -137,20 +116,7 @@ pub fn create_child(P: &OMatrix<f64, Dynamic, Dynamic>, delta: &OVector<f64, Dyn
         }
     }
 
-    let mut coordinates = vec![];
-    for i in 0..(f64::sqrt(child.len() as f64) as usize) {
-        for j in 0..(f64::sqrt(child.len() as f64) as usize) {
-            coordinates.push((i,j));
-        }
-    }
-
-    let mut full_child: Vec<_> = binary_child.iter().zip(coordinates.iter()).collect();
-    //let mut full_child: Vec<_> = child.iter().zip(coordinates.iter()).collect();
-    full_child.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
-
-    let new_state = construct_state(full_child, f64::sqrt(size as f64) as usize);
-
-    new_state
+    genome.decode(&binary_child, init_st)
 }
 
 pub fn select_candidates(mut candidates: Vec<State>, to_keep: usize) -> Vec<State> {
@@ -170,37 +136,57 @@ pub fn create_random_edges(n: usize) -> Vec<(f64, (usize, usize))> {
         }
     }
 
-    let mut rng = thread_rng();
-    list.shuffle(&mut rng);
+    withRng(|rng| list.shuffle(rng));
 
     list
 }
 
-pub fn add_parents_small_curri(lambda: usize, n: usize, mut states: Vec<State> ) -> Vec<State> {
+pub fn add_parents_small_curri(lambda: usize, n: usize, mut states: Vec<State>, init_st: &State) -> Vec<State> {
     let to_add = lambda - states.len();
     for i in 0..to_add {
         let list = create_random_edges(n);
         let vec: Vec<(&f64, &(usize, usize))>  = list.iter().map(|(x, y)| (x, y)).collect();
-        let st = construct_state(vec, n);
+        let st = construct_state(vec, n, init_st);
         states.push(st);
     }
 
     states
 }
 
-pub fn launch_CMAES(lambda: usize, restart: i32, fct: usize, size_terminal: usize, verbose: bool, registerName: String) -> State {
-    let mut best_state = State::new();
-    best_state.conj = fct;
+// estimation of distribution: the children are drawn from the mean and empirical covariance of the lambda best graphs met so far
+pub fn launch_gaussian_EDA(init_st: State, lambda: usize, restart: i32, size_terminal: usize, verbose: bool, registerName: String) -> State {
+    let mut best_state = init_st.clone();
     let mut best_score = best_state.score();
 
-    let start_time = Instant::now();
+    let mut start_time = Instant::now();
+
+    let genome = Genome::new(size_terminal);
 
+    // the parents are the pool of the checkpoint
+    let name = checkpointName(&registerName, &init_st);
+    let mut last_checkpoint = Instant::now();
     let mut parents: Vec<State> = vec![];
-    parents = add_parents_small_curri(lambda, size_terminal, parents);
     let mut num_reach = 0;
+    match resumeCheckpoint(&name, &init_st) {
+        Some(cp) => {
+            start_time = cp.restore();
+            best_state = cp.best;
+            best_score = cp.best_score;
+            num_reach = cp.step as i32;
+            parents = cp.pool.into_iter().map(|(_, st)| st).collect();
+        },
+        None => parents = add_parents_small_curri(lambda, size_terminal, parents, &init_st)
+    }
 
     while num_reach < restart {
-        let ensemble: Vec<Vec<f64>> = assemble_encodings(parents.clone());
+        if checkpointDue(&init_st, &mut last_checkpoint) {
+            let mut cp = Checkpoint::new(&start_time, best_score, &best_state, &init_st);
+            cp.step = num_reach as usize;
+            cp.pool = parents.iter().map(|st| (st.score(), st.clone())).collect();
+            saveCheckpoint(&name, &cp);
+        }
+
+        let ensemble: Vec<Vec<f64>> = assemble_encodings(&parents, &genome);
         let parents_matrix: DMatrix<f64> = ensemble_to_DMatrix(&ensemble);
 
         let mean = gaussian_mean(&parents_matrix);
@@ -209,7 +195,7 @@ pub fn launch_CMAES(lambda: usize, restart: i32, fct: usize, size_terminal: usiz
 
         let mut children: Vec<State> = vec![];
         for _ in 0..lambda {
-            let child = create_child(&P, &delta, &mean);
+            let child = create_child(&P, &delta, &mean, &genome, &init_st);
             children.push(child);
         }
 
@@ -222,32 +208,8 @@ pub fn launch_CMAES(lambda: usize, restart: i32, fct: usize, size_terminal: usiz
                 best_score = new_st_score;
                 best_state.best_score = new_st_score;
 
-                let elapsed = start_time.elapsed().as_secs_f64();
-                println!("CMAES best score yet : {} after {}", new_st_score, elapsed);
-                if verbose {
-                    let new_name = registerName.clone() + &*"_evolution".to_string();
-                    writeLine("Conjecture ".to_owned() + &*fct.to_string()
-                                  + " | CMAES best score yet : " + &*new_st_score.to_string()
-                                  + " after " + &*elapsed.to_string()
-                                  + "s, " + &*best_state.n_sommet.to_string()
-                                  + " vertices\n", new_name);
-                                  }
-
-                if new_st_score > 0.0001 {
-                    let elapsed = start_time.elapsed().as_secs_f64();
-                    if verbose {
-                        writeLine("Conjecture ".to_owned() + &*fct.to_string()
-                                      + "\n        Counterexample found in " + &*elapsed.to_string()
-                                      + "s: best score = " + &*new_st_score.to_string()
-                                      + "\n        With CMAES restart" + &*restart.to_string()
-                                      + ", " + &*best_state.n_sommet.to_string()
-                                      + "\n\n", registerName.clone());
-                                      }
-                    println!("Conjecture {}\n   Counter-example found with CMAES restart {} after {}s\n\n", fct, restart, elapsed);
-
-                    graphToDot::adj_matrix_to_dot(new_st.adj_mat.clone(), &*format!("{}/conj{}", registerName, best_state.conj));
-                    saveMatrix::save_matrix(&*format!("{}/conj{}", registerName, best_state.conj), new_st.adj_mat.clone());
-
+                if recordBest(&best_state, new_st_score, &start_time, "EDA", &format!(" generations {}", restart), verbose, &registerName) {
+                    removeCheckpoint(&name);
                     return best_state
                 }
             }
@@ -259,5 +221,317 @@ pub fn launch_CMAES(lambda: usize, restart: i32, fct: usize, size_terminal: usiz
         num_reach += 1;
     }
 
+    removeCheckpoint(&name);
     return best_state
-}
\ No newline at end of file
+}
+// what CMA-ES does once a run has stopped: nothing, IPOP (population doubled at each restart)
+// or BIPOP (alternates the doubled population with small populations and step sizes, each regime getting the same budget)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Restart {
+    None,
+    Ipop,
+    Bipop
+}
+
+// a CMA-ES run, what its checkpoints keep (B and D are recomputed from C when it is resumed)
+pub struct Run {
+    pub lambda : usize,
+    pub sigma : f64,
+    pub mean : DVector<f64>,
+    pub pc : DVector<f64>,
+    pub ps : DVector<f64>,
+    pub C : DMatrix<f64>,
+    pub B : DMatrix<f64>,
+    pub D : DVector<f64>,
+    pub gen : usize,
+    pub eigen_gen : usize,
+    pub history : Vec<f64>
+}
+
+impl Run {
+    // from the mean 0.5 on every edge
+    pub fn new(dim: usize, lambda: usize, sigma: f64) -> Self {
+        Self {
+            lambda,
+            sigma,
+            mean: DVector::from_element(dim, 0.5),
+            pc: DVector::zeros(dim),
+            ps: DVector::zeros(dim),
+            C: DMatrix::identity(dim, dim),
+            B: DMatrix::identity(dim, dim),
+            D: DVector::from_element(dim, 1.0),
+            gen: 0,
+            eigen_gen: 0,
+            history: vec![]
+        }
+    }
+
+    // B and D from C, symmetrized against rounding
+    fn decompose(&mut self) {
+        self.eigen_gen = self.gen;
+        self.C = (&self.C + self.C.transpose())*0.5;
+        let eigen = SymmetricEigen::new(self.C.clone());
+        self.D = eigen.eigenvalues.map(|e| e.max(1e-20).sqrt());
+        self.B = eigen.eigenvectors;
+    }
+}
+
+pub struct CMAES {
+    pub best_yet : f64,
+    pub timeout : f64,
+    pub registerName : String,
+    pub start_time : Instant,
+    pub best_state : State,
+    pub restart : Restart,
+    pub sigma0 : f64,
+    pub evaluations : usize,
+    // restart schedule: number of the current run, its regime and the evaluations when it started,
+    // number of large runs and evaluations spent in each regime of BIPOP
+    pub restarts : usize,
+    pub large : bool,
+    pub run_start : usize,
+    pub large_runs : usize,
+    pub large_evaluations : usize,
+    pub small_evaluations : usize,
+    pub last_checkpoint : Instant
+}
+
+impl CMAES {
+    pub fn new() -> Self {
+        Self {
+            best_yet: f64::NEG_INFINITY,
+            timeout: -1.0,
+            registerName: String::new(),
+            start_time: Instant::now(),
+            best_state: State::new(),
+            restart: Restart::Ipop,
+            sigma0: 0.3,
+            evaluations: 0,
+            restarts: 0,
+            large: true,
+            run_start: 0,
+            large_runs: 0,
+            large_evaluations: 0,
+            small_evaluations: 0,
+            last_checkpoint: Instant::now()
+        }
+    }
+
+    fn out_of_time(&self) -> bool {
+        self.timeout > 0.0 && self.start_time.elapsed().as_secs_f64() > self.timeout
+    }
+
+    // logs a new best graph, returns true if it is a counterexample (which is then saved)
+    fn record(&mut self, st: &State, score: f64, lambda: usize, verbose: bool) -> bool {
+        self.best_yet = score;
+        self.best_state = st.clone();
+        self.best_state.best_score = score;
+
+        let details = format!(" {:?} restart {} (lambda {}, {} evaluations)", self.restart, self.restarts, lambda, self.evaluations);
+        recordBest(st, score, &self.start_time, "CMAES", &details, verbose, &self.registerName)
+    }
+
+    fn save_checkpoint(&self, init_st: &State, r: &Run) {
+        let mut cp = Checkpoint::new(&self.start_time, self.best_yet, &self.best_state, init_st);
+        cp.step = r.gen;
+        cp.vectors = vec![
+            ("schedule".to_string(), [self.restarts, self.large as usize, self.run_start, self.large_runs, self.large_evaluations, self.small_evaluations, self.evaluations]
+                .iter().map(|&x| x as f64).collect()),
+            ("run".to_string(), vec![r.lambda as f64, r.sigma, r.gen as f64]),
+            ("mean".to_string(), r.mean.as_slice().to_vec()),
+            ("pc".to_string(), r.pc.as_slice().to_vec()),
+            ("ps".to_string(), r.ps.as_slice().to_vec()),
+            ("C".to_string(), r.C.as_slice().to_vec()),
+            ("history".to_string(), r.history.clone())
+        ];
+        saveCheckpoint(&checkpointName(&self.registerName, init_st), &cp);
+    }
+
+    // the restart schedule and the run of a checkpoint, None if it does not fit the genome of dim coordinates
+    fn restore(&mut self, cp: &Checkpoint, dim: usize) -> Option<Run> {
+        let schedule = cp.vector("schedule")?;
+        let run = cp.vector("run")?;
+        let (mean, pc, ps, C) = (cp.vector("mean")?, cp.vector("pc")?, cp.vector("ps")?, cp.vector("C")?);
+        if schedule.len() != 7 || run.len() != 3 || mean.len() != dim || pc.len() != dim || ps.len() != dim || C.len() != dim*dim {
+            return None
+        }
+
+        self.restarts = schedule[0] as usize;
+        self.large = schedule[1] != 0.0;
+        self.run_start = schedule[2] as usize;
+        self.large_runs = schedule[3] as usize;
+        self.large_evaluations = schedule[4] as usize;
+        self.small_evaluations = schedule[5] as usize;
+        self.evaluations = schedule[6] as usize;
+
+        let mut r = Run::new(dim, run[0] as usize, run[1]);
+        r.gen = run[2] as usize;
+        r.mean = DVector::from_column_slice(mean);
+        r.pc = DVector::from_column_slice(pc);
+        r.ps = DVector::from_column_slice(ps);
+        r.C = DMatrix::from_column_slice(dim, dim, C);
+        r.history = cp.vector("history").unwrap_or(&[]).to_vec();
+        r.decompose();
+        Some(r)
+    }
+
+    // runs r (weighted recombination, cumulative step-size adaptation, rank-one and rank-mu covariance updates)
+    // until the best score stagnates, the covariance degenerates or the time is out; returns true on a counterexample
+    pub fn run(&mut self, init_st: &State, mut r: Run, verbose: bool) -> bool {
+        let genome = Genome::new(init_st.size_terminal);
+        let dim = genome.len();
+        if genome.is_empty() {
+            return false
+        }
+        let d = dim as f64;
+        let lambda = r.lambda;
+
+        let mu = (lambda/2).max(1);
+        let mut weights: Vec<f64> = (0..mu).map(|i| (mu as f64 + 0.5).ln() - ((i+1) as f64).ln()).collect();
+        let sum: f64 = weights.iter().sum();
+        weights = weights.iter().map(|w| w/sum).collect();
+        let mueff = 1.0/weights.iter().map(|w| w*w).sum::<f64>();
+
+        let cc = (4.0 + mueff/d)/(d + 4.0 + 2.0*mueff/d);
+        let cs = (mueff + 2.0)/(d + mueff + 5.0);
+        let c1 = 2.0/((d + 1.3).powi(2) + mueff);
+        let cmu = (1.0 - c1).min(2.0*(mueff - 2.0 + 1.0/mueff)/((d + 2.0).powi(2) + mueff));
+        let damps = 1.0 + 2.0*(((mueff - 1.0)/(d + 1.0)).sqrt() - 1.0).max(0.0) + cs;
+        let chi_n = d.sqrt()*(1.0 - 1.0/(4.0*d) + 1.0/(21.0*d*d));
+
+        // stops once the best score of a generation has not changed for stall generations
+        let stall = 10 + (30.0*d/lambda as f64).ceil() as usize;
+
+        while !self.out_of_time() {
+            let dist = StandardNormal;
+            let mut population: Vec<(f64, DVector<f64>)> = vec![];
+            for _ in 0..lambda {
+                let z = DVector::from_fn(dim, |_, _| withRng(|rng| dist.sample(rng)));
+                let y = &r.B*r.D.component_mul(&z);
+                let x: DVector<f64> = &r.mean + r.sigma*&y;
+                let st = genome.decode(x.as_slice(), init_st);
+                let score = st.score();
+                self.evaluations += 1;
+
+                if score > self.best_yet && self.record(&st, score, lambda, verbose) {
+                    return true
+                }
+                population.push((score, y));
+            }
+            population.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+            let mut y_w = DVector::zeros(dim);
+            for (w, (_, y)) in weights.iter().zip(&population) {
+                y_w += *w*y;
+            }
+            r.mean += r.sigma*&y_w;
+
+            // C^-1/2 y_w = B D^-1 B^T y_w
+            let c_inv_sqrt_y_w = &r.B*(r.B.transpose()*&y_w).component_div(&r.D);
+            r.ps = (1.0 - cs)*&r.ps + (cs*(2.0 - cs)*mueff).sqrt()*c_inv_sqrt_y_w;
+            let hsig = r.ps.norm()/(1.0 - (1.0 - cs).powi(2*(r.gen as i32 + 1))).sqrt()/chi_n < 1.4 + 2.0/(d + 1.0);
+            let h = if hsig { 1.0 } else { 0.0 };
+            r.pc = (1.0 - cc)*&r.pc + h*(cc*(2.0 - cc)*mueff).sqrt()*&y_w;
+
+            let mut rank_mu = DMatrix::zeros(dim, dim);
+            for (w, (_, y)) in weights.iter().zip(&population) {
+                rank_mu += *w*y*y.transpose();
+            }
+            r.C = (1.0 - c1 - cmu)*&r.C + c1*(&r.pc*r.pc.transpose() + (1.0 - h)*cc*(2.0 - cc)*&r.C) + cmu*rank_mu;
+            r.sigma *= ((cs/damps)*(r.ps.norm()/chi_n - 1.0)).exp();
+            r.gen += 1;
+
+            // the O(dim^3) decomposition is refreshed every lambda/(c1 + cmu)/dim/10 evaluations,
+            // which keeps its cost at O(dim^2) per evaluation
+            if ((r.gen - r.eigen_gen)*lambda) as f64 > lambda as f64/(c1 + cmu)/d/10.0 {
+                r.decompose();
+            }
+
+            r.history.push(population[0].0);
+            if r.history.len() > stall {
+                let recent = &r.history[r.history.len() - stall..];
+                let max = recent.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let min = recent.iter().cloned().fold(f64::INFINITY, f64::min);
+                if max == min || max - min < 1e-12 {
+                    break
+                }
+            }
+            if r.D.max()/r.D.min() > 1e7 || r.sigma*r.D.max() < 1e-12 {
+                break
+            }
+
+            if checkpointDue(init_st, &mut self.last_checkpoint) {
+                self.save_checkpoint(init_st, &r);
+            }
+        }
+        false
+    }
+
+    // runs CMA-ES and restarts it until a counterexample is found or the time is out, a checkpoint resumes the run it was taken in
+    pub fn search(&mut self, init_st: State, verbose: bool) -> State {
+        let dim = Genome::new(init_st.size_terminal).len();
+        let default_lambda = 4 + (3.0*(dim.max(1) as f64).ln()) as usize;
+
+        let name = checkpointName(&self.registerName, &init_st);
+        let mut resumed = None;
+        if let Some(cp) = resumeCheckpoint(&name, &init_st) {
+            self.start_time = cp.restore();
+            self.best_yet = cp.best_score;
+            self.best_state = cp.best.clone();
+            resumed = self.restore(&cp, dim);
+        }
+
+        while !self.out_of_time() {
+            let r = match resumed.take() {
+                Some(r) => r,
+                None => {
+                    if self.restart == Restart::None && self.restarts > 0 {
+                        break
+                    }
+
+                    self.large = self.restart != Restart::Bipop || self.restarts == 0 || self.large_evaluations <= self.small_evaluations;
+                    let (lambda, sigma) = if self.large {
+                        let lambda = default_lambda << self.large_runs;
+                        self.large_runs += 1;
+                        (lambda, self.sigma0)
+                    } else {
+                        // small regime of BIpop, between the default and half of the current large population
+                        let u = randomF64();
+                        let large_lambda = (default_lambda << (self.large_runs - 1)) as f64;
+                        let lambda = (default_lambda as f64*(0.5*large_lambda/default_lambda as f64).powf(u*u)) as usize;
+                        (lambda.max(default_lambda), self.sigma0*10f64.powf(-2.0*u))
+                    };
+                    println!("CMAES {:?} restart {} : lambda {}, sigma {}", self.restart, self.restarts, lambda, sigma);
+                    self.run_start = self.evaluations;
+                    Run::new(dim, lambda, sigma)
+                }
+            };
+
+            if self.run(&init_st, r, verbose) {
+                break
+            }
+            // the first run does not count for BIpop, both regimes start from it
+            if self.restarts > 0 {
+                if self.large {
+                    self.large_evaluations += self.evaluations - self.run_start;
+                } else {
+                    self.small_evaluations += self.evaluations - self.run_start;
+                }
+            }
+            self.restarts += 1;
+        }
+
+        removeCheckpoint(&name);
+        self.best_state.clone()
+    }
+}
+
+pub fn launch_CMAES(init_st: State, restart: Restart, sigma0: f64, timeout: f64, verbose: bool, registerName: String) -> State {
+    let mut expe = CMAES::new();
+    expe.timeout = timeout;
+    expe.restart = restart;
+    expe.sigma0 = sigma0;
+    expe.registerName = registerName;
+
+    expe.search(init_st, verbose)
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+use crate::models::conjectures::conjectures_wagner_1::State;
+use crate::methods::ILS::{create_random_graph, perturbation, set_edge, Kick};
+use crate::tools::resultSaver::recordBest;
+use crate::tools::calc::{randomF64, uniform};
+use crate::tools::checkpoint::{Checkpoint, checkpointName, checkpointDue, saveCheckpoint, resumeCheckpoint, removeCheckpoint};
+
+// how two parents are combined: SubgraphExchange copies into the second parent the subgraph induced by a BFS ball of the first,
+// VertexSet splits the vertices between the parents and takes each edge from the parent owning its endpoints, Mixed draws one of them per child
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Crossover {
+    SubgraphExchange,
+    VertexSet,
+    Mixed
+}
+
+pub struct GA {
+    pub best_yet : f64,
+    pub timeout : f64,
+    pub registerName : String,
+    pub start_time : Instant,
+    pub best_state : State,
+    pub crossover : Crossover,
+    pub tournament : usize,
+    pub mutation_rate : f64,
+    pub kick : Kick
+}
+
+fn edge(st: &State, i: usize, j: usize) -> f64 {
+    if st.adj_mat[(i, j)] > 0.0 { 1.0 } else { 0.0 }
+}
+
+// vertices of a at distance at most a random radius of a random vertex, at most half of the graph
+fn bfs_ball(a: &State) -> Vec<bool> {
+    let n = a.n_sommet;
+    let mut inside = vec![false; n];
+    let size = 1 + uniform((n/2).max(1));
+    let root = uniform(n);
+
+    let mut queue = VecDeque::from([root]);
+    inside[root] = true;
+    let mut count = 1;
+    while let Some(v) = queue.pop_front() {
+        for w in 0..n {
+            if count >= size {
+                return inside
+            }
+            if !inside[w] && a.adj_mat[(v, w)] > 0.0 {
+                inside[w] = true;
+                count += 1;
+                queue.push_back(w);
+            }
+        }
+    }
+    inside
+}
+
+pub fn subgraph_exchange(a: &State, b: &State) -> State {
+    let mut child = b.clone();
+    let n = a.n_sommet.min(b.n_sommet);
+    let inside = bfs_ball(a);
+    for i in 0..n {
+        for j in (i+1)..n {
+            if inside[i] && inside[j] {
+                set_edge(&mut child, i, j, edge(a, i, j));
+            }
+        }
+    }
+    child
+}
+
+// an edge between the two sides comes from either parent
+pub fn vertex_set_crossover(a: &State, b: &State) -> State {
+    let mut child = b.clone();
+    let n = a.n_sommet.min(b.n_sommet);
+    let from_a: Vec<bool> = (0..n).map(|_| randomF64() < 0.5).collect();
+    for i in 0..n {
+        for j in (i+1)..n {
+            let take_a = if from_a[i] == from_a[j] { from_a[i] } else { randomF64() < 0.5 };
+            if take_a {
+                set_edge(&mut child, i, j, edge(a, i, j));
+            }
+        }
+    }
+    child
+}
+
+impl GA {
+    pub fn new() -> Self {
+        Self {
+            best_yet: f64::NEG_INFINITY,
+            timeout: -1.0,
+            registerName: String::new(),
+            start_time: Instant::now(),
+            best_state: State::new(),
+            crossover: Crossover::Mixed,
+            tournament: 3,
+            mutation_rate: 0.2,
+            kick: Kick::Edges(2)
+        }
+    }
+
+    // logs a new best graph, returns true if it is a counterexample (which is then saved)
+    fn record(&mut self, st: &State, score: f64, generation: usize, verbose: bool) -> bool {
+        self.best_yet = score;
+        self.best_state = st.clone();
+        self.best_state.best_score = score;
+
+        let details = format!(" {:?} crossover, child {}", self.crossover, generation);
+        recordBest(st, score, &self.start_time, "GA", &details, verbose, &self.registerName)
+    }
+
+    // index of the best of tournament members drawn uniformly
+    fn select(&self, population: &[(f64, State)]) -> usize {
+        let mut best = uniform(population.len());
+        for _ in 1..self.tournament {
+            let other = uniform(population.len());
+            if population[other].0 > population[best].0 {
+                best = other;
+            }
+        }
+        best
+    }
+
+    fn child(&self, a: &State, b: &State) -> State {
+        let crossover = match self.crossover {
+            Crossover::Mixed => if randomF64() < 0.5 { Crossover::SubgraphExchange } else { Crossover::VertexSet },
+            c => c
+        };
+        let mut child = match crossover {
+            Crossover::SubgraphExchange => subgraph_exchange(a, b),
+            _ => vertex_set_crossover(a, b)
+        };
+        if randomF64() < self.mutation_rate {
+            child = perturbation(child, self.kick);
+        }
+        child.seq = Vec::new();
+        child.repair();
+        child
+    }
+
+    // steady state: each child replaces the worst graph of the population if it is better, so the best one is never lost
+    pub fn search(&mut self, init_st: State, pop_size: usize, verbose: bool) -> State {
+        let n = init_st.size_terminal;
+        let name = checkpointName(&self.registerName, &init_st);
+        let mut last_checkpoint = Instant::now();
+
+        let mut population: Vec<(f64, State)> = vec![];
+        let mut generation = 0;
+        match resumeCheckpoint(&name, &init_st) {
+            Some(cp) => {
+                self.start_time = cp.restore();
+                self.best_yet = cp.best_score;
+                self.best_state = cp.best;
+                generation = cp.step;
+                population = cp.pool;
+            },
+            None => {
+                for _ in 0..pop_size.max(2) {
+                    let st = create_random_graph(n, &init_st);
+                    let score = st.best_score;
+                    if score > self.best_yet && self.record(&st, score, generation, verbose) {
+                        removeCheckpoint(&name);
+                        return self.best_state.clone()
+                    }
+                    population.push((score, st));
+                }
+            }
+        }
+
+        while self.timeout <= 0.0 || self.start_time.elapsed().as_secs_f64() < self.timeout {
+            if checkpointDue(&init_st, &mut last_checkpoint) {
+                let mut cp = Checkpoint::new(&self.start_time, self.best_yet, &self.best_state, &init_st);
+                cp.step = generation;
+                cp.pool = population.clone();
+                saveCheckpoint(&name, &cp);
+            }
+            generation += 1;
+
+            let a = self.select(&population);
+            let b = self.select(&population);
+            let child = self.child(&population[a].1, &population[b].1);
+            let score = child.score();
+
+            if score > self.best_yet && self.record(&child, score, generation, verbose) {
+                break
+            }
+
+            let worst = (0..population.len()).fold(0, |w, i| if population[i].0 < population[w].0 { i } else { w });
+            if score > population[worst].0 {
+                population[worst] = (score, child);
+            }
+        }
+
+        removeCheckpoint(&name);
+        self.best_state.clone()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn launch_ga(init_st: State, pop_size: usize, tournament: usize, mutation_rate: f64, crossover: Crossover, kick: Kick, timeout: f64, verbose: bool, registerName: String) -> State {
+    let mut expe = GA::new();
+    expe.timeout = timeout;
+    expe.tournament = tournament;
+    expe.mutation_rate = mutation_rate;
+    expe.crossover = crossover;
+    expe.kick = kick;
+    expe.registerName = registerName;
+
+    expe.search(init_st, pop_size, verbose)
+}
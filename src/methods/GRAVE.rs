@@ -1,9 +1,30 @@
 use std::collections::HashMap;
-use crate::tools::calc::softmaxChoice;
+use crate::methods::playout::search_playout;
 use std::time::Instant;
-use crate::tools::resultSaver::writeLine;
+use crate::tools::resultSaver::recordBest;
 use crate::models::conjectures::conjectures_wagner_1::{State, Move};
-use crate::tools::{graphToDot, saveMatrix};
+use crate::tools::saveTransTable::{readTransTable, saveTransTable};
+use crate::tools::calc::{uniform, heuristicWeights, softmax};
+use crate::tools::checkpoint::{Checkpoint, checkpointName, checkpointDue, saveCheckpoint, resumeCheckpoint, removeCheckpoint};
+
+// how a node of the tree picks its move: Uct(c) is value + c*sqrt(ln N / n), Rave blends the value with the AMAF value of the node,
+// Grave with the AMAF value of its last ancestor of more than REF playouts, Puct(c) is value + c*prior*sqrt(N) / (1 + n)
+// with the softmax of the heuristic as prior; the moves never tried are tried first, except with Puct where they get the value of the node
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Selection {
+    Uct(f64),
+    Rave,
+    Grave,
+    Puct(f64)
+}
+
+// Mean backs up the average score of a move, Max the best score met through it (single-player MCTS),
+// wins then holds this best score instead of a sum
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Backup {
+    Mean,
+    Max
+}
 
 #[derive(Clone)]
 pub struct transEntry{
@@ -22,7 +43,12 @@ pub struct GRAVE{
     pub registerName: String,
     pub timeout: f64,
     pub best_state: State,
+    pub selection: Selection,
+    pub backup: Backup
+}
 
+fn empty_entry() -> transEntry {
+    transEntry{wins : HashMap::new(), playouts : HashMap::new(), winsAMAF : HashMap::new(), playoutsAMAF : HashMap::new(), allplayouts : 0}
 }
 
 impl GRAVE{
@@ -34,60 +60,84 @@ impl GRAVE{
             best_score_yet: f64::NEG_INFINITY,
             timeout: -1.0,
             registerName: String::new(),
-            best_state: State::new()
+            best_state: State::new(),
+            selection: Selection::Grave,
+            backup: Backup::Mean
         }
     }
 
-    pub fn playout(&mut self, mut st: State, heuristic_w : f64) -> State {
-        let mut best_state: State = st.clone();
-        let mut best_state_score = best_state.score();
+    // adds res to the statistics of m
+    fn backup(&self, wins: &mut HashMap<Move, f64>, playouts: &mut HashMap<Move, i32>, m: Move, res: f64) {
+        let w = match (self.backup, wins.get(&m)) {
+            (_, None) => res,
+            (Backup::Mean, Some(f)) => f + res,
+            (Backup::Max, Some(f)) => f.max(res)
+        };
+        wins.insert(m, w);
+        *playouts.entry(m).or_insert(0) += 1;
+    }
 
-        while !st.terminal() {
-            let mut heuristic_possible = true;
-            let moves = st.legal_moves();
+    // value and number of playouts of m, None if it was never played
+    fn value(&self, wins: &HashMap<Move, f64>, playouts: &HashMap<Move, i32>, m: &Move) -> Option<(f64, f64)> {
+        let w = *wins.get(m)?;
+        let p = *playouts.get(m)? as f64;
+        match self.backup {
+            Backup::Mean => Some((w/p, p)),
+            Backup::Max => Some((w, p))
+        }
+    }
 
-            if moves.len() == 0 {
-                return st
-            }
-            let mut i = ((moves.len() as f64)*rand::random::<f64>()) as usize;
-
-            if heuristic_w != 0.0 {
-                let mut weights = Vec::new();
-                for &m in &moves{
-                    let v = heuristic_w*st.heuristic(m);
-                    if v.is_nan() {
-                        heuristic_possible = false
-                    }
-                    weights.push(v);
-                }
+    // value of the node t itself, over all its moves, 0 before any playout
+    fn node_value(&self, t: &transEntry) -> f64 {
+        if t.wins.is_empty() {
+            return 0.0;
+        }
+        match self.backup {
+            Backup::Mean => t.wins.values().sum::<f64>() / t.playouts.values().sum::<i32>().max(1) as f64,
+            Backup::Max => t.wins.values().cloned().fold(f64::NEG_INFINITY, f64::max)
+        }
+    }
 
-                if heuristic_possible {
-                    i = softmaxChoice(weights);
-                }
+    // softmax of the heuristic of the moves, uniform without heuristic
+    fn priors(&self, st: &mut State, moves: &[Move], heuristic_w: f64) -> Vec<f64> {
+        if heuristic_w != 0.0 {
+            if let Some(weights) = heuristicWeights(st, moves, heuristic_w) {
+                return softmax(&weights, 1.0);
             }
+        }
+        vec![1.0 / moves.len() as f64; moves.len()]
+    }
 
-            let mv = moves[i];
-            st.play(mv);
-
-            if State::CONSIDER_NON_TERM {
-                let sc = st.score();
-                if sc > best_state_score {
-                    best_state_score = sc;
-                    best_state = st.clone();
-                    best_state.best_score = best_state_score;
+    // score of m at the node t for the selection policy, tref holds the AMAF statistics of Rave and Grave
+    fn selection_value(&self, st: &mut State, t: &transEntry, tref: &transEntry, m: Move, prior: f64, heuristic_w: f64) -> f64 {
+        let (mean, p) = self.value(&t.wins, &t.playouts, &m).unwrap_or((0.0, 0.0));
+        let n = t.allplayouts as f64;
+        match self.selection {
+            Selection::Uct(c) => {
+                if p == 0.0 {
+                    return 1000000000000.0;
                 }
+                mean + c*(n.ln()/p).sqrt()
+            },
+            Selection::Puct(c) => {
+                let q = if p == 0.0 { self.node_value(t) } else { mean };
+                q + c*prior*n.sqrt()/(1.0 + p)
+            },
+            Selection::Rave | Selection::Grave => {
+                let Some((AMAF, pa)) = self.value(&tref.winsAMAF, &tref.playoutsAMAF, &m) else {
+                    return 1000000000000.0;
+                };
+                let mut Bm = pa/(pa + p);
+                if heuristic_w != 0.0 {
+                    // an undefined heuristic gives no bias
+                    let h = st.heuristic(m);
+                    if !h.is_nan() {
+                        Bm = pa/(pa + p + h*pa*p);
+                    }
+                }
+                (1.0 - Bm) * mean + Bm*AMAF
             }
-
-            if st.score() > 0.0001 {
-                return best_state
-            }
-        }
-
-        if State::CONSIDER_NON_TERM {
-            return best_state
         }
-
-        return st
     }
 
     pub fn GRAVE(&mut self, mut st: State, initref: transEntry, heuristic_w: f64, playout_heuristic_w: f64, verbose: bool) -> (f64, State) {
@@ -102,32 +152,8 @@ impl GRAVE{
                 self.best_state = st.clone();
                 self.best_state.best_score = self.best_score_yet;
 
-                let elapsed = self.start_time.elapsed().as_secs_f64();
-                println!("GRAVE best score yet : {} after {}", res, elapsed);
-                if verbose {
-                    let new_name = self.registerName.clone() + &*"_evolution".to_string();
-                    writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                  + " | GRAVE best score yet : " + &*res.to_string()
-                                  + " after " + &*elapsed.to_string()
-                                  + "s, " + &*self.best_state.n_sommet.to_string()
-                                  + " vertices\n", new_name);
-                                  }
-
-                if res > 0.0001 {
-                    let elapsed = self.start_time.elapsed().as_secs_f64();
-                    if verbose {
-                        writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                      + "\n        Counterexample found in " + &*elapsed.to_string()
-                                      + "s: best score = " + &*res.to_string()
-                                      + "\n        With GRAVE "
-                                      + ", " + &*self.best_state.n_sommet.to_string()
-                                      + " vertices\n\n", self.registerName.clone());
-                                      }
-                    println!("Conjecture {}\n   Counter-example found with GRAVE after {}s\n\n", st.conj, elapsed);
-
-                    graphToDot::adj_matrix_to_dot(st.adj_mat.clone(), &*format!("{}/conj{}", self.registerName, st.conj));
-                    saveMatrix::save_matrix(&*format!("{}/conj{}", self.registerName, st.conj), st.adj_mat.clone());
-                    }
+                let details = format!(" ({:?} selection, {:?} backup)", self.selection, self.backup);
+                recordBest(&st, res, &self.start_time, "GRAVE", &details, verbose, &self.registerName);
             }
 
             return (res, st);
@@ -136,40 +162,23 @@ impl GRAVE{
         if self.transTable.contains_key(&st.seq) {
             let mut new_st = st.clone();
             let t = self.transTable.get(&st.seq).unwrap().clone();
-            if t.allplayouts > self.REF {
+            // RAVE always uses the AMAF statistics of the node itself
+            if t.allplayouts > self.REF || self.selection == Selection::Rave {
                 tref = t.clone();
             }
 
-            let mut best_value = f64::NEG_INFINITY;
-            let mut best_move = moves[0].clone();
-            for m in moves{
-
-                let mut mean = 0.0;
-                let mut p = 0.0;
-                let mut w = 0.0;
-                if t.wins.get(&m).is_some() {
-                    w = *t.wins.get(&m).unwrap();
-                    p = *t.playouts.get(&m).unwrap() as f64;
-                    mean = w/p;
-                }
-
-                let mut value = 1000000000000.0;
-
-                if tref.winsAMAF.get(&m).is_some() {
-                    let wa = *tref.winsAMAF.get(&m).unwrap();
-                    let pa = *tref.playoutsAMAF.get(&m).unwrap() as f64;
-                    let mut Bm = pa/(pa + p);
-                    if heuristic_w != 0.0 {
-                        Bm = pa/(pa + p + st.heuristic(m)*pa*p);
-                    }
-
-                    let AMAF = wa/pa;
-                    value = (1.0 - Bm) * mean + Bm*AMAF;
-                }
+            let priors = match self.selection {
+                Selection::Puct(_) => self.priors(&mut st, &moves, heuristic_w),
+                _ => vec![0.0; moves.len()]
+            };
 
+            let mut best_value = f64::NEG_INFINITY;
+            let mut best_move = moves[0];
+            for (m, prior) in moves.into_iter().zip(priors) {
+                let value = self.selection_value(&mut st, &t, &tref, m, prior, heuristic_w);
                 if value > best_value {
                     best_value = value;
-                    best_move = m.clone();
+                    best_move = m;
                 }
             }
 
@@ -177,42 +186,14 @@ impl GRAVE{
             let (res, resState) = self.GRAVE(new_st.clone(), tref, heuristic_w, playout_heuristic_w, verbose);
 
             //update transtable[board] with res
-
-            let mut n : &i32 = &0;
-            let mut f : &f64 = &0.0;
-            let m = best_move.clone();
-
-
             let mut w = t.wins.clone();
-            if w.contains_key(&m) {
-                f = w.get(&m).unwrap();
-            }
-            w.insert(m, f+ res);
-
-            n= &0;
             let mut p = t.playouts.clone();
-            if p.contains_key(&m) {
-                n = p.get(&m).unwrap();
-            }
-            p.insert(m, n+1);
-
+            self.backup(&mut w, &mut p, best_move, res);
 
             let mut wa = t.winsAMAF.clone();
             let mut pa = t.playoutsAMAF.clone();
-            for i in st.seq.len()..resState.seq.len() {
-                let m = resState.seq[i];
-
-                f= &0.0;
-                if wa.contains_key(&m) {
-                    f = wa.get(&m).unwrap();
-                }
-                wa.insert(m, f + res);
-
-                n= &0;
-                if pa.contains_key(&m) {
-                    n = pa.get(&m).unwrap();
-                }
-                pa.insert(m, n + 1);
+            for &m in &resState.seq[st.seq.len()..] {
+                self.backup(&mut wa, &mut pa, m, res);
             }
 
             let entry = transEntry{wins : w, playouts : p, winsAMAF : wa , playoutsAMAF : pa, allplayouts : t.allplayouts +1};
@@ -222,9 +203,9 @@ impl GRAVE{
         } else {
             let mut new_st = st.clone();
             let moves = new_st.legal_moves();
-            let m = moves[((moves.len() as f64)*rand::random::<f64>()) as usize];
+            let m = moves[uniform(moves.len())];
             new_st.play(m);
-            let mut pl = self.playout(new_st, playout_heuristic_w);
+            let mut pl = search_playout(new_st, playout_heuristic_w, true);
             res = pl.score();
 
             if res > self.best_score_yet {
@@ -232,56 +213,18 @@ impl GRAVE{
                 self.best_state = pl.clone();
                 self.best_state.best_score = self.best_score_yet;
 
-                let elapsed = self.start_time.elapsed().as_secs_f64();
-                println!("GRAVE best score yet : {} after {}", res, elapsed);
-                if verbose {
-                    let new_name = self.registerName.clone() + &*"_evolution".to_string();
-                    writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                  + " | GRAVE best score yet : " + &*res.to_string()
-                                  + " after " + &*elapsed.to_string()
-                                  + "s, " + &*self.best_state.n_sommet.to_string()
-                                  + " vertices\n", new_name);
-                                  }
-
-                if res > 0.0001 {
-                    let elapsed = self.start_time.elapsed().as_secs_f64();
-                    if verbose {
-                        writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                      + "\n        Counterexample found in " + &*elapsed.to_string()
-                                      + "s: best score = " + &*res.to_string()
-                                      + "\n        With GRAVE "
-                                      + ", " + &*self.best_state.n_sommet.to_string()
-                                      + " vertices\n\n", self.registerName.clone());
-                                      }
-                    println!("Conjecture {}\n   Counter-example found with GRAVE after {}s\n\n", st.conj, elapsed);
-
-                    graphToDot::adj_matrix_to_dot(pl.adj_mat.clone(), &*format!("{}/conj{}", self.registerName, st.conj));
-                    saveMatrix::save_matrix(&*format!("{}/conj{}", self.registerName, st.conj), pl.adj_mat.clone());
-                }
+                let details = format!(" ({:?} selection, {:?} backup)", self.selection, self.backup);
+                recordBest(&pl, res, &self.start_time, "GRAVE", &details, verbose, &self.registerName);
             }
 
             let mut w = HashMap::new();
-            w.insert(m, res);
-
             let mut p = HashMap::new();
-            p.insert(m, 1);
+            self.backup(&mut w, &mut p, m, res);
 
             let mut wa = tref.winsAMAF.clone();
             let mut pa = tref.playoutsAMAF.clone();
-            for i in st.seq.len()..pl.seq.len() {
-                let m = pl.seq[i];
-
-                let mut f : &f64 = &0.0;
-                if wa.contains_key(&m) {
-                    f = wa.get(&m).unwrap();
-                }
-                wa.insert(m, f + res);
-
-                let mut n : &i32 = &0;
-                if pa.contains_key(&m) {
-                    n = pa.get(&m).unwrap();
-                }
-                pa.insert(m, n + 1);
+            for &m in &pl.seq[st.seq.len()..] {
+                self.backup(&mut wa, &mut pa, m, res);
             }
 
             let entry = transEntry{wins : w, playouts : p, winsAMAF : wa , playoutsAMAF : pa, allplayouts : 1};
@@ -290,29 +233,136 @@ impl GRAVE{
             return (res, pl);
         }
     }
+
+    // GRAVE runs from inist until a counterexample or the timeout, every descent goes on with the tree (transTable) of the previous ones
+    // and starts from the AMAF statistics of the root; the tree is saved with the checkpoints, a resumed run goes on with it
+    pub fn search(&mut self, inist: State, heuristic_w: f64, playout_heuristic_w: f64, verbose: bool) -> State {
+        let name = checkpointName(&self.registerName, &inist);
+        let table = format!("{}/table{}", self.registerName, inist.conjecture_name());
+        let mut last_checkpoint = Instant::now();
+        let mut descents = 0;
+        if let Some(cp) = resumeCheckpoint(&name, &inist) {
+            self.start_time = cp.restore();
+            self.best_score_yet = cp.best_score;
+            self.best_state = cp.best;
+            descents = cp.step;
+            // the tree saved with the checkpoint replaces the warm-start table of tableFile
+            self.transTable = readTransTable(&table);
+        }
+
+        let st = loop {
+            let tref = self.transTable.get(&inist.seq).cloned().unwrap_or_else(empty_entry);
+            let (_, st) = self.GRAVE(inist.clone(), tref, heuristic_w, playout_heuristic_w, verbose);
+            descents += 1;
+
+            if st.is_counterexample() || self.start_time.elapsed().as_secs_f64() >= self.timeout {
+                break st
+            }
+
+            if checkpointDue(&inist, &mut last_checkpoint) {
+                let mut cp = Checkpoint::new(&self.start_time, self.best_score_yet, &self.best_state, &inist);
+                cp.step = descents;
+                save_table(&table, &self.transTable);
+                saveCheckpoint(&name, &cp);
+            }
+        };
+
+        removeCheckpoint(&name);
+        st
+    }
 }
 
-pub fn launch_grave(inist: State, rf: i32, heuristic_w: f64, playout_heuristic_w: f64, timeout: f64, verbose: bool, registerName: String) -> State {
+// a tree that cannot be written is reported, the search goes on without it
+fn save_table(name: &str, table: &HashMap<Vec<Move>, transEntry>) {
+    if let Err(e) = saveTransTable(name, table) {
+        println!("error, cannot save the transposition table {}: {}", name, e);
+    }
+}
+
+// starts from the transposition table saved as tableFile (if not empty) and saves the final one next to the results
+#[allow(clippy::too_many_arguments)]
+pub fn launch_grave(inist: State, rf: i32, heuristic_w: f64, playout_heuristic_w: f64, selection: Selection, backup: Backup, tableFile: &str, timeout: f64, verbose: bool, registerName: String) -> State {
     let mut expe = GRAVE::new();
     expe.timeout = timeout;
     expe.registerName = registerName;
     expe.REF = rf;
+    expe.selection = selection;
+    expe.backup = backup;
+    if !tableFile.is_empty() {
+        expe.transTable = readTransTable(tableFile);
+    }
 
-    let tref = transEntry{wins : HashMap::new(), playouts : HashMap::new(), winsAMAF : HashMap::new(), playoutsAMAF : HashMap::new(), allplayouts : 0};
-    let (vic, st) = expe.GRAVE(inist.clone(), tref, heuristic_w, playout_heuristic_w, verbose);
+    let st = expe.search(inist.clone(), heuristic_w, playout_heuristic_w, verbose);
+    save_table(&format!("{}/table{}", expe.registerName, inist.conjecture_name()), &expe.transTable);
+    st
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::conjectures::conjectures_wagner_1::MoveKind;
 
-    if st.score() > 0.0001 {
-        return st
+    fn mv(ind: usize) -> Move {
+        Move{ind, from: ind, to: -1, kind: MoveKind::AddEdge}
     }
 
-    while expe.start_time.elapsed().as_secs_f64() < timeout {
-        let mut tref2 = transEntry{wins : HashMap::new(), playouts : HashMap::new(), winsAMAF : HashMap::new(), playoutsAMAF : HashMap::new(), allplayouts : 0};
-        let (vic, st) = expe.GRAVE(inist.clone(), tref2, heuristic_w, playout_heuristic_w, verbose);
+    fn grave(selection: Selection, backup: Backup) -> GRAVE {
+        let mut g = GRAVE::new();
+        g.selection = selection;
+        g.backup = backup;
+        g
+    }
 
-        if st.score() > 0.0001 {
-            return st
+    // a node where mv(0) was played for each score of scores
+    fn node(g: &GRAVE, scores: &[f64]) -> transEntry {
+        let mut t = empty_entry();
+        for &res in scores {
+            g.backup(&mut t.wins, &mut t.playouts, mv(0), res);
+            t.allplayouts += 1;
         }
+        t
+    }
+
+    #[test]
+    fn max_keeps_the_best_score_and_mean_averages() {
+        let max = grave(Selection::Grave, Backup::Max);
+        let t = node(&max, &[1.0, 3.0, 2.0]);
+        assert_eq!(max.value(&t.wins, &t.playouts, &mv(0)), Some((3.0, 3.0)));
+        assert_eq!(max.node_value(&t), 3.0);
+
+        let mean = grave(Selection::Grave, Backup::Mean);
+        let t = node(&mean, &[1.0, 3.0, 2.0]);
+        assert_eq!(mean.value(&t.wins, &t.playouts, &mv(0)), Some((2.0, 3.0)));
+        assert_eq!(mean.node_value(&t), 2.0);
+        assert_eq!(mean.value(&t.wins, &t.playouts, &mv(1)), None);
+
+        // a fresh node has a finite value
+        assert_eq!(max.node_value(&empty_entry()), 0.0);
+        assert_eq!(mean.node_value(&empty_entry()), 0.0);
     }
 
-    return st;
+    #[test]
+    fn puct_prefers_an_unvisited_move_with_a_high_prior() {
+        let mut st = State::new();
+        for backup in [Backup::Mean, Backup::Max] {
+            let g = grave(Selection::Puct(1.0), backup);
+            let t = node(&g, &[0.5, 0.5, 0.5]);
+            let visited = g.selection_value(&mut st, &t, &t, mv(0), 0.1, 0.0);
+            let unvisited = g.selection_value(&mut st, &t, &t, mv(1), 0.9, 0.0);
+            assert!(unvisited > visited);
+            // before any playout the prior alone orders the moves
+            let fresh = empty_entry();
+            assert!(g.selection_value(&mut st, &fresh, &fresh, mv(1), 0.9, 0.0).is_finite());
+        }
+    }
+
+    #[test]
+    fn uct_tries_unvisited_moves_first() {
+        let mut st = State::new();
+        let g = grave(Selection::Uct(0.5), Backup::Mean);
+        let t = node(&g, &[1.0, 1.0]);
+        let visited = g.selection_value(&mut st, &t, &t, mv(0), 0.0, 0.0);
+        assert!((visited - (1.0 + 0.5*(2f64.ln()/2.0).sqrt())).abs() < 1e-12);
+        assert!(g.selection_value(&mut st, &t, &t, mv(1), 0.0, 0.0) > visited);
+    }
 }
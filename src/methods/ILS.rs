@@ -1,15 +1,12 @@
 use std::time::Instant;
 use rand::prelude::SliceRandom;
-use rand::{Rng, thread_rng};
-use crate::models::conjectures::conjectures_wagner_1::{Move, State};
-use crate::tools::{graphToDot, saveMatrix};
-use crate::tools::resultSaver::writeLine;
+use crate::models::conjectures::conjectures_wagner_1::State;
+use crate::tools::resultSaver::recordBest;
+use crate::tools::calc::{randomF64, uniform, withRng};
+use crate::tools::checkpoint::{Checkpoint, checkpointName, checkpointDue, saveCheckpoint, resumeCheckpoint, removeCheckpoint};
 
-pub fn create_random_regular_graph(n: usize, d: usize, fct: usize) -> State {
-    let mut st = State::new();
-    st.n_sommet = n;
-    st.adj_mat.resize_mut(st.n_sommet, st.n_sommet, 0.0);
-    st.conj = fct;
+pub fn create_random_regular_graph(n: usize, d: usize, init_st: &State) -> State {
+    let mut st = init_st.empty_like(n);
 
     let mut moves = vec![];
     let mut degrees = vec![];
@@ -20,8 +17,7 @@ pub fn create_random_regular_graph(n: usize, d: usize, fct: usize) -> State {
         }
     }
 
-    let mut rng = thread_rng();
-    moves.shuffle(&mut rng);
+    withRng(|rng| moves.shuffle(rng));
 
     for m in moves {
         if degrees[m.0] <= d && degrees[m.1] <= d {
@@ -29,78 +25,136 @@ pub fn create_random_regular_graph(n: usize, d: usize, fct: usize) -> State {
             st.adj_mat[(m.1, m.0)] = 1.0;
             degrees[m.0] += 1;
             degrees[m.1] += 1;
+            st.n_arete += 1;
         }
     }
 
+    st.repair();
     let sc = st.score();
     st.best_score = sc;
 
     st
 }
 
-pub fn create_random_graph(n: usize, fct: usize) -> State {
-    let mut rng = thread_rng();
-
-    let mut st = State::new();
-    st.n_sommet = n;
-    st.conj = fct;
-
-    st.adj_mat.resize_mut(st.n_sommet, st.n_sommet, 0.0);
+pub fn create_random_graph(n: usize, init_st: &State) -> State {
+    let mut st = init_st.empty_like(n);
     for i in 0..n {
         for j in (i + 1)..n {
-            let value = if rng.gen::<f64>() < 0.5 { 0.0 } else { 1.0 };
+            let value = if randomF64() < 0.5 { 0.0 } else { 1.0 };
             st.adj_mat[(i, j)] = value;
             st.adj_mat[(j, i)] = value;
+            st.n_arete += value as usize;
         }
     }
 
+    st.repair();
     let sc = st.score();
     st.best_score = sc;
 
     st
 }
 
-pub fn create_complete_graph(n: usize, fct: usize) -> State {
-    let mut st = State::new();
-    st.n_sommet = n;
-    st.conj = fct;
-
-    st.adj_mat.resize_mut(st.n_sommet, st.n_sommet, 0.0);
+pub fn create_complete_graph(n: usize, init_st: &State) -> State {
+    let mut st = init_st.empty_like(n);
     for i in 0..n {
         for j in (i + 1)..n {
             st.adj_mat[(i, j)] = 1.0;
             st.adj_mat[(j, i)] = 1.0;
+            st.n_arete += 1;
         }
     }
 
+    st.repair();
     let sc = st.score();
     st.best_score = sc;
 
     st
 }
 
-pub fn local_search(st: State) -> State {
-    let mut rng = thread_rng();
+// the edge-toggle neighbourhood shared by the local searches: every pair (i, j), i < j
+pub fn toggle_pairs(n: usize) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..n {
+        for j in (i+1)..n {
+            pairs.push((i, j));
+        }
+    }
+    pairs
+}
 
-    let mut st_clone = st.clone();
-    let mut sc = st_clone.score();
+// st with the pair (i, j) toggled, repaired to its graph class (the toggle is not added to the sequence, which would grow at each step)
+pub fn toggle(st: &State, i: usize, j: usize) -> State {
+    let mut new_st = st.clone();
+    new_st.toggle_arete(i, j);
+    new_st.repair();
+    new_st
+}
 
-    let mut possible_moves = Vec::new();
-    for i in 0..st.n_sommet {
-        for j in (i+1)..st.n_sommet {
-            possible_moves.push((i, j));
+// moves of the local search: Toggle(i, j) is the fixed-size neighbourhood, Pendant(v) attaches a new vertex to v,
+// Delete(v) removes v and Subdivide(i, j) replaces the edge ij by a path through a new vertex
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LocalMove {
+    Toggle(usize, usize),
+    Pendant(usize),
+    Delete(usize),
+    Subdivide(usize, usize)
+}
+
+// toggles only when max_n is 0, otherwise the vertex moves that keep the order between 2 and max_n as well
+pub fn local_moves(st: &State, max_n: usize) -> Vec<LocalMove> {
+    let mut moves: Vec<LocalMove> = toggle_pairs(st.n_sommet).into_iter().map(|(i, j)| LocalMove::Toggle(i, j)).collect();
+    if max_n == 0 {
+        return moves
+    }
+    if st.n_sommet < max_n {
+        for v in 0..st.n_sommet {
+            moves.push(LocalMove::Pendant(v));
+        }
+        for (i, j) in toggle_pairs(st.n_sommet) {
+            if st.adj_mat[(i, j)] > 0.0 {
+                moves.push(LocalMove::Subdivide(i, j));
+            }
         }
     }
+    if st.n_sommet > 2 {
+        for v in 0..st.n_sommet {
+            moves.push(LocalMove::Delete(v));
+        }
+    }
+    moves
+}
+
+// st after m, repaired to its graph class
+pub fn apply_local_move(st: &State, m: LocalMove) -> State {
+    let mut new_st = st.clone();
+    match m {
+        LocalMove::Toggle(i, j) => new_st.toggle_arete(i, j),
+        LocalMove::Pendant(v) => new_st.add_arete(v, -1),
+        LocalMove::Delete(v) => new_st.remove_sommet(v),
+        LocalMove::Subdivide(i, j) => {
+            new_st.remove_arete(i, j);
+            new_st.add_arete(i, -1);
+            let w = new_st.n_sommet - 1;
+            new_st.add_arete(w, j as i32);
+        }
+    }
+    new_st.repair();
+    new_st
+}
+
+// first improvement over the moves of local_moves(st, max_n), in a random order at each pass
+pub fn local_search(st: State, max_n: usize) -> State {
+    let mut st_clone = st.clone();
+    let mut sc = st_clone.score();
 
     let mut possible_improvement = true;
     while possible_improvement {
-        possible_moves.shuffle(&mut rng);
+        let mut possible_moves = local_moves(&st_clone, max_n);
+        withRng(|rng| possible_moves.shuffle(rng));
 
         possible_improvement = false;
-        for m in &possible_moves {
-            let mv = Move{ind: st.n_sommet, from: m.0, to: m.1 as i64};
-            let mut new_st = st.clone();
-            new_st.play(mv);
+        for &m in &possible_moves {
+            let new_st = apply_local_move(&st_clone, m);
 
             let new_st_score = new_st.score();
 
@@ -118,94 +172,148 @@ pub fn local_search(st: State) -> State {
     return st_clone
 }
 
-pub fn perturbation(st: State) -> State {
-    let mut rng = thread_rng();
-
-    let mut pertubated_state = st.clone();
+// random change applied by ILS between two local searches: Edges(k) toggles k pairs, alternately an edge and a non-edge
+// so that an even k keeps the number of edges, DoubleEdgeSwap(k) replaces k times two edges ab, cd by ad, cb (degrees kept),
+// Rewire(k) detaches k vertices and reconnects each to as many random vertices as it had neighbours
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Kick {
+    Edges(usize),
+    DoubleEdgeSwap(usize),
+    Rewire(usize)
+}
 
-    let mut moves = vec![];
-    for i in 0..st.n_sommet {
-        for j in (i+1)..st.n_sommet {
-            moves.push((i, j));
+// sets the pair (i, j) to value (1.0 or 0.0) and keeps n_arete up to date
+pub fn set_edge(st: &mut State, i: usize, j: usize, value: f64) {
+    if (st.adj_mat[(i, j)] > 0.0) != (value > 0.0) {
+        if value > 0.0 {
+            st.n_arete += 1;
+        } else {
+            st.n_arete -= 1;
         }
     }
-    moves.shuffle(&mut rng);
+    st.adj_mat[(i, j)] = value;
+    st.adj_mat[(j, i)] = value;
+}
 
-    let mut m1 = moves[0].0;
-    let mut m2 = moves[0].1;
-    let mut edge = 1.0;
-    if pertubated_state.adj_mat[(m1, m2)] == 1.0 {
-        edge = 0.0;
-    }
-    pertubated_state.adj_mat[(m1, m2)] == edge;
-    pertubated_state.adj_mat[(m2, m1)] == edge;
+fn edge_kick(st: &mut State, k: usize) {
+    let mut pairs = toggle_pairs(st.n_sommet);
+    withRng(|rng| pairs.shuffle(rng));
 
-    let mut i = 2;
-    for &m in &moves[2..] {
-        m1 = m.0;
-        m2 = m.1;
-        if pertubated_state.adj_mat[(m1, m2)] == edge {
-            let mut second_edge = 0.0;
-            if second_edge == edge {
-                second_edge = 1.0;
-            }
-            pertubated_state.adj_mat[(m1, m2)] == second_edge;
-            pertubated_state.adj_mat[(m2, m1)] == second_edge;
+    let mut wanted: Option<f64> = None;
+    let mut kicked = 0;
+    for (i, j) in pairs {
+        if kicked == k {
             break
         }
-
-        i += 1;
+        let value = if st.adj_mat[(i, j)] > 0.0 { 1.0 } else { 0.0 };
+        if wanted.is_none_or(|w| w == value) {
+            set_edge(st, i, j, 1.0 - value);
+            wanted = Some(1.0 - value);
+            kicked += 1;
+        }
     }
+}
 
-    /*let mut m1 = moves[moves.len()-1].0;
-    let mut m2 = moves[moves.len()-1].1;
-    let mut third_edge = 1.0;
-    if pertubated_state.adj_mat[(m1, m2)] == 1.0 {
-        third_edge = 0.0;
-    }
-    pertubated_state.adj_mat[(m1, m2)] == third_edge;
-    pertubated_state.adj_mat[(m2, m1)] == third_edge;
+fn double_edge_swap(st: &mut State, k: usize) {
+    let n = st.n_sommet;
+    for _ in 0..k {
+        let mut edges = Vec::new();
+        for (i, j) in toggle_pairs(n) {
+            if st.adj_mat[(i, j)] > 0.0 {
+                edges.push((i, j));
+                edges.push((j, i));
+            }
+        }
+        if edges.len() < 4 {
+            return
+        }
 
-    for &m in moves.iter().rev() {
-        m1 = m.0;
-        m2 = m.1;
-        if pertubated_state.adj_mat[(m1, m2)] == edge {
-            let mut fourth_edge = 0.0;
-            if fourth_edge == edge {
-                fourth_edge = 1.0;
+        // a few draws of two edges, the swap needs four distinct vertices and ad, cb absent
+        for _ in 0..100 {
+            let (a, b) = edges[uniform(edges.len())];
+            let (c, d) = edges[uniform(edges.len())];
+            if a == c || a == d || b == c || b == d || st.adj_mat[(a, d)] > 0.0 || st.adj_mat[(c, b)] > 0.0 {
+                continue
             }
-            pertubated_state.adj_mat[(m1, m2)] == fourth_edge;
-            pertubated_state.adj_mat[(m2, m1)] == fourth_edge;
+            set_edge(st, a, b, 0.0);
+            set_edge(st, c, d, 0.0);
+            set_edge(st, a, d, 1.0);
+            set_edge(st, c, b, 1.0);
             break
         }
-    }*/
+    }
+}
+
+fn rewire(st: &mut State, k: usize) {
+    let n = st.n_sommet;
+    if n < 2 {
+        return
+    }
+    for _ in 0..k {
+        let v = uniform(n);
+        let mut others: Vec<usize> = (0..n).filter(|&w| w != v).collect();
+        let degree = others.iter().filter(|&&w| st.adj_mat[(v, w)] > 0.0).count();
+        withRng(|rng| others.shuffle(rng));
+        for (rank, &w) in others.iter().enumerate() {
+            set_edge(st, v, w, if rank < degree { 1.0 } else { 0.0 });
+        }
+    }
+}
 
+pub fn perturbation(st: State, kick: Kick) -> State {
+    let mut pertubated_state = st.clone();
+    match kick {
+        Kick::Edges(k) => edge_kick(&mut pertubated_state, k),
+        Kick::DoubleEdgeSwap(k) => double_edge_swap(&mut pertubated_state, k),
+        Kick::Rewire(k) => rewire(&mut pertubated_state, k)
+    }
+
+    pertubated_state.repair();
     let sc = pertubated_state.score();
     pertubated_state.best_score = sc;
 
     pertubated_state
 }
 
-pub fn iterative_local_search(n: usize, d: usize, fct: usize, timeout: f64, verbose: bool, registerName: String) -> State {
-    let start_time = Instant::now();
-
-    //let mut st = create_random_regular_graph(n, d, fct);
-    let mut st = create_random_graph(n, fct);
-    let mut best_state = st.clone();
-    let mut best_score = best_state.best_score;
-    println!("First best_score {}", best_score);
+// starts from a random graph of n vertices, max_n > 0 lets the local search add and delete vertices up to max_n
+#[allow(clippy::too_many_arguments)]
+pub fn iterative_local_search(init_st: State, n: usize, max_n: usize, d: usize, kick: Kick, timeout: f64, verbose: bool, registerName: String) -> State {
+    let mut start_time = Instant::now();
+    let mut last_checkpoint = Instant::now();
+    let name = checkpointName(&registerName, &init_st);
+
+    let (mut st, mut best_state, mut best_score) = match resumeCheckpoint(&name, &init_st) {
+        Some(cp) => {
+            start_time = cp.restore();
+            (cp.position, cp.best, cp.best_score)
+        },
+        None => {
+            //let st = create_random_regular_graph(n, d, &init_st);
+            let st = create_random_graph(n, &init_st);
+            let best_state = st.clone();
+            let best_score = best_state.best_score;
+            if recordBest(&best_state, best_score, &start_time, "ILS", "", verbose, &registerName) {
+                return best_state
+            }
 
-    st = local_search(st);
+            (local_search(st, max_n), best_state, best_score)
+        }
+    };
 
-    while st.best_score <= 0.0001 {
+    while !st.is_counterexample() {
         if start_time.elapsed().as_secs_f64() > timeout && timeout > 0.0 {
+            removeCheckpoint(&name);
             return best_state
         }
 
-        let mut new_st = perturbation(st.clone());
+        if checkpointDue(&st, &mut last_checkpoint) {
+            saveCheckpoint(&name, &Checkpoint::new(&start_time, best_score, &best_state, &st));
+        }
+
+        let mut new_st = perturbation(st.clone(), kick);
         //st = perturbation(st, fct, &mut rng); #RW
 
-        new_st = local_search(new_st);
+        new_st = local_search(new_st, max_n);
         //st = local_search(st, fct, &mut rng); #RW
         //if st.best_score > best_score { #RW
         if new_st.best_score > best_score {
@@ -215,31 +323,8 @@ pub fn iterative_local_search(n: usize, d: usize, fct: usize, timeout: f64, verb
             best_state = new_st.clone();
             best_state.best_score = best_score;
 
-            println!("ILS best score yet : {} after {}", best_score, start_time.elapsed().as_secs_f64());
-            if verbose {
-                let new_name = registerName.clone() + &*"_evolution".to_string();
-                writeLine("Conjecture ".to_owned() + &*fct.to_string()
-                              + " | ILS best score yet : " + &*best_score.to_string()
-                              + " after " + &*start_time.elapsed().as_secs_f64().to_string()
-                              + "s, " + &*best_state.n_sommet.to_string()
-                              + " vertices\n", new_name);
-                              }
-
-            if new_st.best_score > 0.0001 {
-                let elapsed = start_time.elapsed().as_secs_f64();
-                if verbose {
-                    writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                  + "\n        Counterexample found in " + &*elapsed.to_string()
-                                  + "s: best score = " + &*new_st.best_score.to_string()
-                                  + "\n        With ILS"
-                                  + ", " + &*best_state.n_sommet.to_string()
-                                  + " vertices\n\n", registerName.clone());
-                                  }
-                println!("Conjecture {}\n   Counter-example found with ILS after {}s\n", st.conj, elapsed);
-
-                graphToDot::adj_matrix_to_dot(new_st.adj_mat.clone(), &*format!("{}/conj{}", registerName, st.conj));
-                saveMatrix::save_matrix(&*format!("{}/conj{}", registerName, st.conj), new_st.adj_mat.clone());
-
+            if recordBest(&best_state, best_score, &start_time, "ILS", "", verbose, &registerName) {
+                removeCheckpoint(&name);
                 return best_state
             }
         }
@@ -249,5 +334,41 @@ pub fn iterative_local_search(n: usize, d: usize, fct: usize, timeout: f64, verb
         }
     }
 
+    removeCheckpoint(&name);
     st
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::moveGenerator::count_edges;
+    use crate::tools::calc::setSeed;
+
+    #[test]
+    fn kicks_change_the_graph() {
+        setSeed(7);
+        let st = create_random_graph(10, &State::new());
+        for kick in [Kick::Edges(1), Kick::Edges(2), Kick::Edges(4), Kick::DoubleEdgeSwap(2), Kick::Rewire(2)] {
+            let kicked = perturbation(st.clone(), kick);
+            assert_ne!(kicked.adj_mat, st.adj_mat, "{:?}", kick);
+            assert_eq!(kicked.n_arete, count_edges(&kicked), "{:?}", kick);
+        }
+        // an even number of toggles alternates edges and non-edges
+        for k in [2, 4, 6] {
+            assert_eq!(perturbation(st.clone(), Kick::Edges(k)).n_arete, st.n_arete);
+        }
+    }
+
+    #[test]
+    fn swaps_keep_the_degrees() {
+        setSeed(11);
+        let st = create_random_graph(12, &State::new());
+        let swapped = perturbation(st.clone(), Kick::DoubleEdgeSwap(5));
+        assert_eq!(swapped.degrees(), st.degrees());
+        assert_eq!(swapped.n_arete, st.n_arete);
+
+        // a rewired vertex keeps its degree, hence the number of edges
+        let rewired = perturbation(st.clone(), Kick::Rewire(5));
+        assert_eq!(rewired.n_arete, st.n_arete);
+        assert_eq!(count_edges(&rewired), st.n_arete);
+    }
+}
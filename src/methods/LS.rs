@@ -0,0 +1,187 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::Instant;
+use crate::models::conjectures::conjectures_wagner_1::State;
+use crate::methods::ILS::{create_random_graph, toggle_pairs, toggle};
+use crate::tools::resultSaver::recordBest;
+use crate::tools::calc::{randomF64, uniform};
+
+// temperature of simulated annealing after a fraction t in [0, 1] of the steps of a run, starting at t0:
+// Geometric(alpha) multiplies it by alpha at each step, Linear goes down to 0, Exponential(t_end) goes down to t_end,
+// Logarithmic is t0 / ln(e + step)
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Cooling {
+    Geometric(f64),
+    Linear,
+    Exponential(f64),
+    Logarithmic
+}
+
+impl Cooling {
+    pub fn temperature(&self, t0: f64, step: usize, t: f64) -> f64 {
+        match self {
+            Cooling::Geometric(alpha) => t0*alpha.powi(step as i32),
+            Cooling::Linear => t0*(1.0 - t),
+            Cooling::Exponential(t_end) => t0*(t_end/t0).powf(t),
+            Cooling::Logarithmic => t0/(std::f64::consts::E + step as f64).ln()
+        }
+    }
+}
+
+// local searches over the edge toggles of a graph of terminal vertices (the neighbourhood of ILS::local_search)
+pub struct LS {
+    pub best_yet : f64,
+    pub timeout : f64,
+    pub registerName : String,
+    pub start_time : Instant,
+    pub best_state : State
+}
+
+impl LS {
+    pub fn new() -> Self {
+        Self {
+            best_yet: f64::NEG_INFINITY,
+            timeout: -1.0,
+            registerName: String::new(),
+            start_time: Instant::now(),
+            best_state: State::new()
+        }
+    }
+
+    fn out_of_time(&self) -> bool {
+        self.timeout > 0.0 && self.start_time.elapsed().as_secs_f64() > self.timeout
+    }
+
+    // logs a new best graph, returns true if it is a counterexample (which is then saved)
+    pub fn record(&mut self, st: &State, score: f64, name: &str, verbose: bool) -> bool {
+        self.best_yet = score;
+        self.best_state = st.clone();
+        self.best_state.best_score = score;
+
+        recordBest(st, score, &self.start_time, name, "", verbose, &self.registerName)
+    }
+
+    // runs of steps random toggles from t0 down the cooling schedule, a worse graph is accepted with probability exp(delta/T);
+    // every run but the first starts again from the best graph met (if any), until a counterexample is found or the time is out
+    pub fn annealing(&mut self, init_st: State, cooling: Cooling, t0: f64, steps: usize, verbose: bool) -> State {
+        let mut st = create_random_graph(init_st.size_terminal, &init_st);
+        let mut sc = st.best_score;
+        if sc > self.best_yet && self.record(&st, sc, "SA", verbose) {
+            return self.best_state.clone()
+        }
+
+        let pairs = toggle_pairs(st.n_sommet);
+        if pairs.is_empty() {
+            return self.best_state.clone()
+        }
+
+        while !self.out_of_time() {
+            for step in 0..steps {
+                if self.out_of_time() {
+                    break
+                }
+                let temperature = cooling.temperature(t0, step, step as f64/steps as f64);
+
+                let (i, j) = pairs[uniform(pairs.len())];
+                let new_st = toggle(&st, i, j);
+                let new_sc = new_st.score();
+
+                // a graph without a score is never accepted over one with a score
+                let accept = if new_sc >= sc {
+                    true
+                } else if new_sc == f64::NEG_INFINITY || temperature <= 0.0 {
+                    false
+                } else {
+                    randomF64() < ((new_sc - sc)/temperature).exp()
+                };
+
+                if accept {
+                    st = new_st;
+                    sc = new_sc;
+                    if sc > self.best_yet && self.record(&st, sc, "SA", verbose) {
+                        return self.best_state.clone()
+                    }
+                }
+            }
+
+            // until a graph has a score, best_state is the placeholder of LS::new and the run goes on from st
+            if self.best_yet > f64::NEG_INFINITY {
+                st = self.best_state.clone();
+                sc = self.best_yet;
+            }
+        }
+
+        self.best_state.clone()
+    }
+
+    // moves to the best neighbour whose pair was not toggled in the last tenure moves, even when it is worse;
+    // a tabu pair is allowed when it beats the best graph met (aspiration)
+    pub fn tabu(&mut self, init_st: State, tenure: usize, verbose: bool) -> State {
+        let mut st = create_random_graph(init_st.size_terminal, &init_st);
+        let sc = st.best_score;
+        if sc > self.best_yet && self.record(&st, sc, "Tabu", verbose) {
+            return self.best_state.clone()
+        }
+
+        let pairs = toggle_pairs(st.n_sommet);
+        let mut tabu_list: VecDeque<(usize, usize)> = VecDeque::new();
+        let mut tabu_set: HashSet<(usize, usize)> = HashSet::new();
+
+        while !self.out_of_time() {
+            let mut best: Option<((usize, usize), f64, State)> = None;
+            for &(i, j) in &pairs {
+                if self.out_of_time() {
+                    return self.best_state.clone()
+                }
+
+                let new_st = toggle(&st, i, j);
+                let new_sc = new_st.score();
+                if tabu_set.contains(&(i, j)) && new_sc <= self.best_yet {
+                    continue
+                }
+                if best.as_ref().is_none_or(|b| new_sc > b.1) {
+                    best = Some(((i, j), new_sc, new_st));
+                }
+            }
+
+            // every move is tabu, the oldest one is released
+            let Some((pair, new_sc, new_st)) = best else {
+                match tabu_list.pop_front() {
+                    Some(pair) => { tabu_set.remove(&pair); },
+                    None => break
+                }
+                continue
+            };
+
+            st = new_st;
+            tabu_list.push_back(pair);
+            tabu_set.insert(pair);
+            if tabu_list.len() > tenure {
+                if let Some(old) = tabu_list.pop_front() {
+                    tabu_set.remove(&old);
+                }
+            }
+
+            if new_sc > self.best_yet && self.record(&st, new_sc, "Tabu", verbose) {
+                return self.best_state.clone()
+            }
+        }
+
+        self.best_state.clone()
+    }
+}
+
+pub fn launch_annealing(init_st: State, cooling: Cooling, t0: f64, steps: usize, timeout: f64, verbose: bool, registerName: String) -> State {
+    let mut expe = LS::new();
+    expe.timeout = timeout;
+    expe.registerName = registerName;
+
+    expe.annealing(init_st, cooling, t0, steps, verbose)
+}
+
+pub fn launch_tabu(init_st: State, tenure: usize, timeout: f64, verbose: bool, registerName: String) -> State {
+    let mut expe = LS::new();
+    expe.timeout = timeout;
+    expe.registerName = registerName;
+
+    expe.tabu(init_st, tenure, verbose)
+}
@@ -1,15 +1,18 @@
 use crate::models::conjectures::conjectures_wagner_1::{State};
-use crate::tools::calc::softmaxChoice;
-use crate::tools::resultSaver::writeLine;
+use crate::methods::playout::search_playout;
+use crate::tools::resultSaver::{logBest, saveCounterexample};
 use std::time::Instant;
-use crate::tools::{graphToDot, saveMatrix};
+use crate::tools::checkpoint::{Checkpoint, checkpointName, checkpointDue, saveCheckpoint, resumeCheckpoint, removeCheckpoint};
 
 pub struct NMCS{
     pub best_yet : f64,
     pub timeout : f64,
     pub registerName : String,
     pub start_time : Instant,
-    pub best_state: State
+    pub best_state: State,
+    pub level : i8,
+    pub last_checkpoint : Instant,
+    pub resume : Option<Checkpoint>
 }
 
 impl NMCS{
@@ -19,64 +22,49 @@ impl NMCS{
             best_yet: f64::NEG_INFINITY,
             timeout: -1.0,
             registerName: String::new(),
-            best_state: State::new()
+            best_state: State::new(),
+            level: 0,
+            last_checkpoint: Instant::now(),
+            resume: None
         }
     }
 
-    pub fn playout(&mut self, mut st: State, heuristic_w : f64) -> State {
-        let mut best_state: State = st.clone();
-        let mut best_state_score = best_state.score();
-
-        while !st.terminal() {
-            let moves = st.legal_moves();
-            if moves.len() == 0 {
-                break
-            }
-
-            let mut i = ((moves.len() as f64)*rand::random::<f64>()) as usize;
-
-            if heuristic_w != 0.0 {
-                let mut weights = Vec::new();
-                for &m in &moves {
-                    weights.push(heuristic_w*st.heuristic(m));
-                }
-
-                i = softmaxChoice(weights);
-            }
-
-            let mv = moves[i];
-            st.play(mv);
-
-            if State::CONSIDER_NON_TERM {
-                let sc = st.score();
-                if sc > best_state_score {
-                    best_state_score = sc;
-                    best_state = st.clone();
-                    best_state.best_score = best_state_score;
-                }
-            }
+    // logs a new best state of a search of the NMCS family, returns true if it is a counterexample (which is then saved)
+    pub fn record(&mut self, best_state: &State, name: &str, n: i8, verbose: bool) -> bool {
+        let best_state_score = best_state.best_score;
+        if best_state_score > self.best_yet {
+            self.best_yet = best_state_score;
+            self.best_state = best_state.clone();
+            self.best_state.best_score = self.best_yet;
 
-            if st.score() > 0.0001 {
-                return best_state
-            }
+            logBest(best_state, best_state_score, &self.start_time, name, verbose, &self.registerName);
         }
-
-        if State::CONSIDER_NON_TERM{
-            return best_state;
-        }
-        return st;
+        saveCounterexample(best_state, best_state_score, &self.start_time, name, &format!(" level {}", n), verbose, &self.registerName)
     }
 
     pub fn nmcs(&mut self, mut st: State, n : i8, heuristic_w : f64, verbose : bool) -> State {
         let mut best_state: State = st.clone();
-        let mut best_state_score = best_state.score();
+        let mut best_state_score = if st.consider_non_term { st.score() } else { f64::NEG_INFINITY };
+
+        // a resumed search goes on at the top level from the position and the move of its checkpoint
+        let mut skip = 0;
+        if n == self.level {
+            if let Some(mut cp) = self.resume.take() {
+                st = cp.position;
+                skip = cp.step;
+                if let Some((score, state)) = cp.pool.pop() {
+                    best_state = state;
+                    best_state_score = score;
+                }
+            }
+        }
 
         while !st.terminal(){
             let moves = st.legal_moves();
             if moves.len() == 0 {
                 break
             }
-            for &mv in &moves{
+            for (i, &mv) in moves.iter().enumerate().skip(skip) {
                 if self.start_time.elapsed().as_secs_f64() > self.timeout && self.timeout > 0.0 {
                     return best_state
                 }
@@ -84,7 +72,7 @@ impl NMCS{
                 let mut new_st = st.clone();
                 new_st.play(mv);
                 if n <= 1 {
-                    new_st = self.playout(new_st, heuristic_w);
+                    new_st = search_playout(new_st, heuristic_w, true);
                 } else {
                     new_st = self.nmcs(new_st, n-1, heuristic_w, verbose);
                 }
@@ -95,67 +83,252 @@ impl NMCS{
                     best_state_score = new_st_score;
                     best_state.best_score = best_state_score;
 
-                    if best_state_score > self.best_yet {
-                        self.best_yet = best_state_score;
-                        self.best_state = best_state.clone();
-                        self.best_state.best_score = self.best_yet;
-
-                        let elapsed = self.start_time.elapsed().as_secs_f64();
-                        println!("NMCS best score yet : {} after {}", best_state_score, elapsed);
-                        if verbose {
-                            let new_name = self.registerName.clone() + &*"_evolution".to_string();
-                            writeLine("Conjecture ".to_owned() + &*best_state.conj.to_string()
-                                          + " | NMCS best score yet : " + &*best_state_score.to_string()
-                                          + " after " + &*elapsed.to_string()
-                                          + "s, " + &*best_state.n_sommet.to_string()
-                                          + " vertices\n", new_name);
-                                          }
+                    if self.record(&best_state, "NMCS", n, verbose) {
+                        return self.best_state.clone()
+                    }
+                }
+
+                if n == self.level && checkpointDue(&st, &mut self.last_checkpoint) {
+                    let mut cp = Checkpoint::new(&self.start_time, self.best_yet, &self.best_state, &st);
+                    cp.step = i + 1;
+                    cp.pool.push((best_state_score, best_state.clone()));
+                    saveCheckpoint(&checkpointName(&self.registerName, &st), &cp);
+                }
+            }
+            skip = 0;
+
+            // no child beats the current state, which is then the best one
+            if best_state.seq.len() <= st.seq.len() {
+                break
+            }
+
+            st.play(best_state.seq[st.seq.len()]);
+        }
+
+        if st.consider_non_term {
+            return self.best_state.clone()
+        }
+
+        return st
+    }
+
+    // keeps the width best children of the states of the beam at each step, a child is valued by the search one level below
+    pub fn beam_nmcs(&mut self, st: State, n : i8, width : usize, heuristic_w : f64, verbose : bool) -> State {
+        if n <= 0 {
+            return search_playout(st, heuristic_w, true);
+        }
+
+        let mut best_state: State = st.clone();
+        let mut best_state_score = if st.consider_non_term { st.score() } else { f64::NEG_INFINITY };
+        let mut beam = vec![(best_state_score, st.clone())];
+
+        // a resumed search goes on at the top level from the beam of its checkpoint
+        if n == self.level {
+            if let Some(cp) = self.resume.take() {
+                beam = cp.pool;
+                best_state = cp.best;
+                best_state_score = cp.best_score;
+            }
+        }
+
+        loop {
+            let mut candidates: Vec<(f64, State)> = Vec::new();
+            for (_, b) in &beam {
+                if b.terminal() {
+                    continue
+                }
+                for mv in b.legal_moves() {
+                    if self.start_time.elapsed().as_secs_f64() > self.timeout && self.timeout > 0.0 {
+                        return best_state
                     }
 
-                    if new_st_score > 0.0001 {
-                        let elapsed = self.start_time.elapsed().as_secs_f64();
-                        if verbose {
-                            writeLine("Conjecture ".to_owned() + &*best_state.conj.to_string()
-                                          + "\n        Counterexample found in " + &*elapsed.to_string()
-                                          + "s: best score = " + &*new_st_score.to_string()
-                                          + "\n        With NMCS level " + &*n.to_string()
-                                          + ", " + &*best_state.n_sommet.to_string()
-                                          + " vertices \n\n", self.registerName.clone());
+                    let mut child = b.clone();
+                    child.play(mv);
+                    let res = self.beam_nmcs(child.clone(), n-1, width, heuristic_w, verbose);
+                    let res_score = res.score();
 
+                    if res_score > best_state_score {
+                        best_state = res;
+                        best_state_score = res_score;
+                        best_state.best_score = best_state_score;
+
+                        if self.record(&best_state, "Beam NMCS", n, verbose) {
+                            return self.best_state.clone()
                         }
-                        println!("Conjecture {}\n   Counter-example found with NMCS level {} after {}s\n\n", best_state.conj, n, elapsed);
+                    }
+                    candidates.push((res_score, child));
+                }
+            }
 
-                        graphToDot::adj_matrix_to_dot(best_state.adj_mat.clone(), &*format!("{}/conj{}", self.registerName, best_state.conj));
-                        saveMatrix::save_matrix(&*format!("{}/conj{}", self.registerName, best_state.conj), best_state.adj_mat.clone());
+            if candidates.is_empty() {
+                break
+            }
+            candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+            candidates.truncate(width.max(1));
+            beam = candidates;
 
-                        return self.best_state.clone()
+            if n == self.level && checkpointDue(&st, &mut self.last_checkpoint) {
+                let mut cp = Checkpoint::new(&self.start_time, self.best_yet, &self.best_state, &st);
+                cp.pool = beam.clone();
+                saveCheckpoint(&checkpointName(&self.registerName, &st), &cp);
+            }
+        }
+
+        if best_state_score == f64::NEG_INFINITY {
+            return st
+        }
+        best_state
+    }
+
+    // NMCS where every move is first valued by quick playouts, only the moves above the prune quantile get the search one level below
+    pub fn lazy_nmcs(&mut self, mut st: State, n : i8, quick : usize, prune : f64, heuristic_w : f64, verbose : bool) -> State {
+        let mut best_state: State = st.clone();
+        let mut best_state_score = if st.consider_non_term { st.score() } else { f64::NEG_INFINITY };
+
+        // a resumed search goes on at the top level from the position of its checkpoint
+        if n == self.level {
+            if let Some(mut cp) = self.resume.take() {
+                st = cp.position;
+                if let Some((score, state)) = cp.pool.pop() {
+                    best_state = state;
+                    best_state_score = score;
+                }
+            }
+        }
+
+        while !st.terminal() {
+            let moves = st.legal_moves();
+            if moves.is_empty() {
+                break
+            }
+
+            let mut children: Vec<(f64, State)> = Vec::new();
+            for &mv in &moves {
+                let mut child = st.clone();
+                child.play(mv);
+
+                let mut value = f64::NEG_INFINITY;
+                for _ in 0..quick.max(1) {
+                    if self.start_time.elapsed().as_secs_f64() > self.timeout && self.timeout > 0.0 {
+                        return best_state
+                    }
+
+                    let pl = search_playout(child.clone(), heuristic_w, true);
+                    let pl_score = pl.score();
+                    value = value.max(pl_score);
+
+                    if pl_score > best_state_score {
+                        best_state = pl;
+                        best_state_score = pl_score;
+                        best_state.best_score = best_state_score;
+
+                        if self.record(&best_state, "Lazy NMCS", n, verbose) {
+                            return self.best_state.clone()
+                        }
                     }
                 }
+                children.push((value, child));
             }
 
-            if State::CONSIDER_NON_TERM {
-                if best_state.seq.len() == st.seq.len() {
-                    break
+            if n > 1 {
+                let mut values: Vec<f64> = children.iter().map(|(v, _)| *v).collect();
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let threshold = values[((prune * values.len() as f64) as usize).min(values.len() - 1)];
+
+                for (value, child) in children {
+                    if value < threshold {
+                        continue
+                    }
+
+                    let res = self.lazy_nmcs(child, n-1, quick, prune, heuristic_w, verbose);
+                    let res_score = res.score();
+                    if res_score > best_state_score {
+                        best_state = res;
+                        best_state_score = res_score;
+                        best_state.best_score = best_state_score;
+
+                        if self.record(&best_state, "Lazy NMCS", n, verbose) {
+                            return self.best_state.clone()
+                        }
+                    }
                 }
             }
 
+            if best_state.seq.len() <= st.seq.len() {
+                break
+            }
             st.play(best_state.seq[st.seq.len()]);
+
+            if n == self.level && checkpointDue(&st, &mut self.last_checkpoint) {
+                let mut cp = Checkpoint::new(&self.start_time, self.best_yet, &self.best_state, &st);
+                cp.pool.push((best_state_score, best_state.clone()));
+                saveCheckpoint(&checkpointName(&self.registerName, &st), &cp);
+            }
         }
 
-        if State::CONSIDER_NON_TERM{
-            return self.best_state.clone()
+        if best_state_score == f64::NEG_INFINITY {
+            return st
         }
+        best_state
+    }
+}
 
-        return st
+// a search of the NMCS family run from a checkpoint goes on with its budget and best state, and removes the checkpoint once finished
+fn launch(mut expe: NMCS, init_st: State, registerName: String, search: impl FnOnce(&mut NMCS, State) -> State) -> State {
+    let name = checkpointName(&registerName, &init_st);
+    if let Some(cp) = resumeCheckpoint(&name, &init_st) {
+        expe.start_time = cp.restore();
+        expe.best_yet = cp.best_score;
+        expe.best_state = cp.best.clone();
+        expe.resume = Some(cp);
     }
+    expe.registerName = registerName;
+
+    let st = search(&mut expe, init_st);
+    removeCheckpoint(&name);
+    st
 }
 
 pub fn launch_nmcs(init_st: State, level: i8, heuristic_w: f64, verbose: bool, timeout: f64, registerName: String) -> State {
     let mut expe = NMCS::new();
     expe.timeout = timeout;
-    expe.registerName = registerName;
+    expe.level = level;
 
-    let st = expe.nmcs(init_st, level, heuristic_w, verbose);
+    launch(expe, init_st, registerName, |e, st| e.nmcs(st, level, heuristic_w, verbose))
+}
+
+pub fn launch_beam_nmcs(init_st: State, level: i8, width: usize, heuristic_w: f64, verbose: bool, timeout: f64, registerName: String) -> State {
+    let mut expe = NMCS::new();
+    expe.timeout = timeout;
+    expe.level = level;
 
-    return st;
+    launch(expe, init_st, registerName, |e, st| e.beam_nmcs(st, level, width, heuristic_w, verbose))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn launch_lazy_nmcs(init_st: State, level: i8, quick: usize, prune: f64, heuristic_w: f64, verbose: bool, timeout: f64, registerName: String) -> State {
+    let mut expe = NMCS::new();
+    expe.timeout = timeout;
+    expe.level = level;
+
+    launch(expe, init_st, registerName, |e, st| e.lazy_nmcs(st, level, quick, prune, heuristic_w, verbose))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_only_nmcs_reaches_a_terminal_graph() {
+        // no child may beat the prefix when only terminal graphs are scored
+        let dir = "nmcsTerminalOnly";
+        let _ = std::fs::create_dir_all(format!("savedMatrix/{}", dir));
+        let mut st = State::new();
+        st.conj = 5;
+        st.size_terminal = 8;
+        st.consider_non_term = false;
+        let res = launch_nmcs(st, 1, 0.0, false, 5.0, dir.to_string());
+        let _ = std::fs::remove_dir_all(format!("savedMatrix/{}", dir));
+        assert!(res.terminal() || res.legal_moves().is_empty());
+    }
 }
@@ -1,9 +1,11 @@
-use crate::models::conjectures::conjectures_wagner_1::{State, Move};
+use crate::models::conjectures::conjectures_wagner_1::{State};
 use std::collections::HashMap;
-use crate::tools::resultSaver::writeLine;
+use crate::tools::resultSaver::recordBest;
 use std::time::Instant;
-use rand::{Rng, thread_rng};
-use crate::tools::{graphToDot, saveMatrix};
+use crate::tools::calc::samplerProbs;
+use crate::methods::playout::{playout, NrpaPolicy};
+use crate::tools::savePolicy::{readPolicy, savePolicy};
+use crate::models::policyEncoding::PolicyKey;
 
 pub(crate) static PLAYOUT: usize = 100;
 pub struct NRPA{
@@ -11,7 +13,10 @@ pub struct NRPA{
     pub timeout : f64,
     pub registerName : String,
     pub start_time : Instant,
-    pub best_state: State
+    pub best_state: State,
+    pub beta: f64,
+    pub tau: f64,
+    pub policy: HashMap<PolicyKey, f64>
 }
 
 impl NRPA{
@@ -21,109 +26,63 @@ impl NRPA{
             best_yet: f64::NEG_INFINITY,
             timeout: -1.0,
             registerName: String::new(),
-            best_state: State::new()
+            best_state: State::new(),
+            beta: 0.0,
+            tau: 1.0,
+            policy: HashMap::new()
         }
     }
 
-    pub fn random_move(&self, moves: Vec<Move>, policy: &mut HashMap<Move, f64>) -> Move {
-        let mut rng = thread_rng();
-
-        let mut sum: f64 = 0.0;
-        for &mv  in &moves {
-            match policy.get(&mv){
-                Some(v) => sum += v.exp(),
-                None => {policy.insert(mv, 0.0); sum += 1.0}
-            };
-
-        }
-
-        let stop = sum * rng.gen::<f64>();;
-        sum = 0.0;
-        for &mv in &moves {
-            sum += policy.get(&mv).unwrap().exp();
-            if sum > stop {
-                return mv;
-            }
+    pub fn name(&self) -> &'static str {
+        if self.beta == 0.0 && self.tau == 1.0 {
+            "NRPA"
+        } else {
+            "GNRPA"
         }
-
-        return moves[0];
     }
 
-    pub fn playout(&self, mut st : State, mut policy : HashMap<Move, f64>) -> State {
-        let mut best_state: State = st.clone();
-        let mut best_state_score = best_state.score();
-
-        while !st.terminal() {
-            let moves: Vec<Move> = st.legal_moves();
-            if moves.len() == 0 {
-                break;
-            }
-            let mv = self.random_move(moves, &mut policy);
-            st.play(mv);
-
-            if State::CONSIDER_NON_TERM {
-                let sc = st.score();
-                if sc > best_state_score {
-                    best_state_score = sc;
-                    best_state = st.clone();
-                    best_state.best_score = sc;
-                }
-            }
-
-            if st.score() > 0.0001 {
-                return best_state
-            }
-        }
-
-        if State::CONSIDER_NON_TERM{
-            return best_state;
-        }
-
-        return st;
-    }
-
-    pub fn adapt(&self, mut policy: HashMap<Move, f64>, st: &mut State, ini_state: State) -> HashMap<Move, f64> {
+    // gradient step on the log-probability of the sequence of st under the distribution of the playouts (top_k included),
+    // plain NRPA when beta = 0 and tau = 1; with a temperature of 0 the playouts are greedy and the step is the one of temperature 1
+    pub fn adapt(&self, policy: HashMap<PolicyKey, f64>, st: &mut State, ini_state: State) -> HashMap<PolicyKey, f64> {
         let mut s: State = ini_state.clone();
-        let mut polp: HashMap<Move, f64> = policy.clone();
+        let mut pol = NrpaPolicy{weights: policy, beta: self.beta, tau: self.tau};
+        let mut polp: HashMap<PolicyKey, f64> = pol.weights.clone();
 
         for best in &mut st.seq[..] {
             let moves = s.legal_moves();
-            let mut sum = 0.0;
-            for &m in &moves {
-                match policy.get(&m){
-                    Some(v) => sum += v.exp(),
-                    None => {policy.insert(m, 0.0); sum += 1.0}
-                };
-            }
-
-            for &m in &moves {
-                match polp.get(&m){
-                    Some(v) => polp.insert(m, v - policy.get(&m).unwrap().exp()/sum),
-                    None => polp.insert(m, -policy.get(&m).unwrap().exp()/sum)
-                };
-
+            let logits = pol.logits(&mut s, &moves);
+            let probs = samplerProbs(&logits, &s.sampler);
+            let temperature = if s.sampler.temperature > 0.0 { s.sampler.temperature } else { 1.0 };
+            let step = 1.0 / (self.tau * temperature);
+
+            // moves sharing a key share its weight, so their gradients add up
+            let keys = s.policy_encoding.keys(&s, &moves);
+            for (k, key) in keys.iter().enumerate() {
+                *polp.entry(*key).or_insert(0.0) -= step * probs[k];
+                if moves[k] == *best {
+                    *polp.entry(*key).or_insert(0.0) += step;
+                }
             }
 
-            polp.insert(*best, polp.get(&best).unwrap() + 1.0);
             s.play(*best);
         }
-        return polp;
+        polp
     }
 
-    pub fn nrpa(&mut self, level : i8, mut policy: HashMap<Move, f64>, ini_state : State, initial: bool, verbose: bool) -> State {
+    pub fn nrpa(&mut self, level : i8, mut policy: HashMap<PolicyKey, f64>, ini_state : State, initial: bool, verbose: bool) -> State {
         let mut st: State = ini_state.clone();
         let mut stscore : f64 = st.score();
 
         if level == 0 || self.start_time.elapsed().as_secs_f64() > self.timeout {
-            return self.playout(st, policy);
+            return playout(st, &mut NrpaPolicy{weights: policy, beta: self.beta, tau: self.tau}, true);
         }
 
         for i in 0..PLAYOUT {
             if initial {
-                println!("NRPA loop {}, best score : {} {}", i, stscore, stscore);
+                println!("{} loop {}, best score : {} {}", self.name(), i, stscore, stscore);
             }
 
-            let pol: HashMap<Move, f64> = policy.clone();
+            let pol: HashMap<PolicyKey, f64> = policy.clone();
             let mut s = self.nrpa(level-1, pol, ini_state.clone(), false, verbose);
             let s_score = s.score();
 
@@ -136,52 +95,52 @@ impl NRPA{
                     self.best_state = s.clone();
                     self.best_state.best_score = self.best_yet;
 
-                    let elapsed = self.start_time.elapsed().as_secs_f64();
-                    println!("NRPA best score yet : {}", stscore);
-                    if verbose {
-                        let new_name = self.registerName.clone() + &*"_evolution".to_string();
-                        writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                      + " | NRPA best score yet : " + &*stscore.to_string()
-                                      + " after " + &*elapsed.to_string()
-                                      + "s, " + &*st.n_sommet.to_string()
-                                      + " vertices\n", new_name);
-                                      }
-
-                    if s_score > 0.0001 {
-                        let elapsed = self.start_time.elapsed().as_secs_f64();
-                        if verbose {
-                            writeLine("Conjecture ".to_owned() + &*st.conj.to_string()
-                                          + "\n        Counterexample found in " + &*elapsed.to_string()
-                                          + "s: best score = " + &*s_score.to_string()
-                                          + "\n        With NRPA level " + &*level.to_string()
-                                          + ", " + &*st.n_sommet.to_string()
-                                          + " vertices\n\n", self.registerName.clone());
-                                          }
-                        println!("Conjecture {}\n   Counter-example found with NRPA level {} after {}s\n", st.conj, level, elapsed);
-
-                        graphToDot::adj_matrix_to_dot(s.adj_mat.clone(), &*format!("{}/conj{}", self.registerName, st.conj));
-                        saveMatrix::save_matrix(&*format!("{}/conj{}", self.registerName, st.conj), s.adj_mat.clone());
-
+                    if recordBest(&s, stscore, &self.start_time, self.name(), &format!(" level {}", level), verbose, &self.registerName) {
                         return self.best_state.clone()
                     }
                 }
             }
             policy = self.adapt(policy, &mut st, ini_state.clone());
+            if initial {
+                self.policy = policy.clone();
+            }
         }
 
         return self.best_state.clone()
     }
-}
 
+    // starts from the policy saved as policyFile (if not empty) and saves the learned one next to the results
+    pub fn run(&mut self, level : i8, ini_state : State, policyFile: &str, verbose: bool) -> State {
+        if !policyFile.is_empty() {
+            self.policy = readPolicy(policyFile);
+        }
+
+        let st = self.nrpa(level, self.policy.clone(), ini_state.clone(), true, verbose);
+        let policy_name = format!("{}/policy{}", self.registerName, ini_state.conjecture_name());
+        if let Err(e) = savePolicy(&policy_name, &self.policy) {
+            println!("error, cannot save the policy {}: {}", policy_name, e);
+        }
+        st
+    }
+}
 
-pub fn launch_nrpa(level: i8, ini_state: State, timeout: f64, verbose: bool, registerName: String) -> State {
-    let policy = HashMap::new();
 
+pub fn launch_nrpa(level: i8, ini_state: State, policyFile: &str, timeout: f64, verbose: bool, registerName: String) -> State {
     let mut expe = NRPA::new();
     expe.timeout = timeout;
     expe.registerName = registerName;
 
-    let st = expe.nrpa(level, policy, ini_state, true, verbose);
+    expe.run(level, ini_state, policyFile, verbose)
+}
 
-    return st;
-}
\ No newline at end of file
+// NRPA with a bias beta * heuristic and a temperature tau, the policy starts from policyFile if it is not empty
+#[allow(clippy::too_many_arguments)]
+pub fn launch_gnrpa(level: i8, ini_state: State, beta: f64, tau: f64, policyFile: &str, timeout: f64, verbose: bool, registerName: String) -> State {
+    let mut expe = NRPA::new();
+    expe.timeout = timeout;
+    expe.registerName = registerName;
+    expe.beta = beta;
+    expe.tau = tau;
+
+    expe.run(level, ini_state, policyFile, verbose)
+}
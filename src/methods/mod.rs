@@ -3,4 +3,7 @@ pub(crate) mod NRPA;
 pub(crate) mod BFS;
 pub(crate) mod GRAVE;
 pub(crate) mod ILS;
-pub(crate) mod CMAES;
\ No newline at end of file
+pub(crate) mod CMAES;
+pub(crate) mod playout;
+pub(crate) mod GA;
+pub(crate) mod LS;
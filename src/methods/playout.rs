@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use crate::models::conjectures::conjectures_wagner_1::{State, Move};
+use crate::tools::calc::{softmaxChoice, heuristicWeights, randomF64, uniform, Sampler};
+use crate::models::policyEncoding::PolicyKey;
+
+pub trait PlayoutPolicy {
+    // index in moves of the move played next from st
+    fn choose(&mut self, st: &mut State, moves: &[Move]) -> usize;
+}
+
+// policy of the playouts of NMCS, GRAVE and BFS (st.playout), the heuristic weight is the one of the search
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Playout {
+    Uniform,
+    HeuristicSoftmax,
+    EpsilonGreedy(f64)
+}
+
+impl Playout {
+    pub fn policy(&self, heuristic_w: f64) -> Box<dyn PlayoutPolicy> {
+        match *self {
+            Playout::Uniform => Box::new(Uniform),
+            Playout::HeuristicSoftmax => Box::new(HeuristicSoftmax{heuristic_w}),
+            Playout::EpsilonGreedy(epsilon) => Box::new(EpsilonGreedy{epsilon, heuristic_w})
+        }
+    }
+}
+
+pub struct Uniform;
+
+// softmax over heuristic_w * heuristic, uniform when heuristic_w is 0 or no heuristic is defined
+pub struct HeuristicSoftmax {
+    pub heuristic_w: f64
+}
+
+// softmax over the weights learned by NRPA for the keys of the moves (see PolicyEncoding), unseen keys start at 0,
+// GNRPA divides them by the temperature tau and adds the bias beta * heuristic
+pub struct NrpaPolicy {
+    pub weights: HashMap<PolicyKey, f64>,
+    pub beta: f64,
+    pub tau: f64
+}
+
+// best heuristic move (drawn among the ties), or a uniform one with probability epsilon
+pub struct EpsilonGreedy {
+    pub epsilon: f64,
+    pub heuristic_w: f64
+}
+
+impl PlayoutPolicy for Uniform {
+    fn choose(&mut self, _st: &mut State, moves: &[Move]) -> usize {
+        uniform(moves.len())
+    }
+}
+
+impl PlayoutPolicy for HeuristicSoftmax {
+    fn choose(&mut self, st: &mut State, moves: &[Move]) -> usize {
+        if self.heuristic_w != 0.0 {
+            if let Some(weights) = heuristicWeights(st, moves, self.heuristic_w) {
+                return softmaxChoice(weights, &st.sampler);
+            }
+        }
+        uniform(moves.len())
+    }
+}
+
+impl NrpaPolicy {
+    pub fn logits(&mut self, st: &mut State, moves: &[Move]) -> Vec<f64> {
+        let mut logits = Vec::new();
+        for key in st.policy_encoding.keys(st, moves) {
+            logits.push(*self.weights.entry(key).or_insert(0.0) / self.tau);
+        }
+        if self.beta != 0.0 {
+            if let Some(bias) = heuristicWeights(st, moves, self.beta) {
+                for (l, b) in logits.iter_mut().zip(bias) {
+                    *l += b;
+                }
+            }
+        }
+        logits
+    }
+}
+
+impl PlayoutPolicy for NrpaPolicy {
+    fn choose(&mut self, st: &mut State, moves: &[Move]) -> usize {
+        let logits = self.logits(st, moves);
+        softmaxChoice(logits, &st.sampler)
+    }
+}
+
+impl PlayoutPolicy for EpsilonGreedy {
+    fn choose(&mut self, st: &mut State, moves: &[Move]) -> usize {
+        if randomF64() < self.epsilon {
+            return uniform(moves.len());
+        }
+        match heuristicWeights(st, moves, self.heuristic_w) {
+            // a zero temperature draws uniformly among the best weights
+            Some(weights) => softmaxChoice(weights, &Sampler{temperature: 0.0, top_k: 0, gumbel: false}),
+            None => uniform(moves.len())
+        }
+    }
+}
+
+// plays moves chosen by policy until st is terminal or has no move left,
+// early_stop ends it on the first counterexample and with consider_non_term the best state met is returned
+pub fn playout(mut st: State, policy: &mut dyn PlayoutPolicy, early_stop: bool) -> State {
+    let mut best_state: State = st.clone();
+    let mut best_state_score = best_state.score();
+
+    while !st.terminal() {
+        let moves = st.legal_moves();
+        if moves.is_empty() {
+            break
+        }
+
+        let i = policy.choose(&mut st, &moves);
+        st.play(moves[i]);
+
+        if st.consider_non_term {
+            let sc = st.score();
+            if sc > best_state_score {
+                best_state_score = sc;
+                best_state = st.clone();
+                best_state.best_score = sc;
+            }
+        }
+
+        if early_stop && st.is_counterexample() {
+            break
+        }
+    }
+
+    if st.consider_non_term {
+        return best_state
+    }
+    st
+}
+
+// playout of a tree search with the policy st.playout
+pub fn search_playout(st: State, heuristic_w: f64, early_stop: bool) -> State {
+    let mut policy = st.playout.policy(heuristic_w);
+    playout(st, policy.as_mut(), early_stop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn epsilon_greedy_breaks_ties_randomly() {
+        let mut st = State::new().empty_like(4);
+        let moves = st.legal_moves();
+        assert!(moves.len() > 1);
+        let mut policy = EpsilonGreedy{epsilon: 0.0, heuristic_w: 0.0};
+        let chosen: HashSet<usize> = (0..200).map(|_| policy.choose(&mut st, &moves)).collect();
+        assert!(chosen.len() > 1);
+    }
+
+    #[test]
+    fn playouts_reach_the_terminal_size() {
+        let mut st = State::new();
+        st.size_terminal = 6;
+        st.consider_non_term = false;
+        for kind in [Playout::Uniform, Playout::HeuristicSoftmax, Playout::EpsilonGreedy(0.1)] {
+            st.playout = kind;
+            let pl = search_playout(st.clone(), 1.0, false);
+            assert!(pl.terminal() || pl.legal_moves().is_empty());
+        }
+    }
+}
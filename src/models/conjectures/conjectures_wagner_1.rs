@@ -1,13 +1,37 @@
 extern crate nalgebra;
 
+use std::cell::RefCell;
 use nalgebra::{DMatrix, DVector};
-use nalgebra::linalg::SymmetricEigen;
+use crate::models::moveGenerator::{GraphMode, ConnectedMoves, MoveGenerator, bfs_forest};
+use crate::models::invariants::InvariantCache;
+use crate::models::spectral::GraphMatrix;
+use crate::tools::calc::Sampler;
+use crate::methods::playout::Playout;
+use crate::models::policyEncoding::PolicyEncoding;
+use crate::models::conjectures::objective::{Objective, Aggregation, Normalization, Degenerate, BoundTerm, declared_form, declared_tolerance};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum MoveKind {
+    AddEdge,
+    RemoveEdge,
+    ToggleEdge,
+    RemoveVertex
+}
+
+// what to do with a disconnected graph
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Connectivity {
+    Ignore,
+    Reject,
+    Repair
+}
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct Move{
     pub ind : usize,
     pub from : usize,
-    pub to: i64
+    pub to: i64,
+    pub kind: MoveKind
 }
 #[derive(Clone)]
 pub struct State{
@@ -17,12 +41,26 @@ pub struct State{
     pub size_terminal: usize,
     pub best_score: f64,
     pub seq: Vec<Move>,
-    pub conj: usize
+    pub conj: usize,
+    pub mode: GraphMode,
+    pub max_edits: usize,
+    pub connectivity: Connectivity,
+    pub spectral: GraphMatrix,
+    pub objective: Option<Objective>,
+    pub aggregation: Option<Aggregation>,
+    pub tolerance: Option<f64>,
+    pub normalization: Normalization,
+    pub degenerate: Degenerate,
+    pub sampler: Sampler,
+    pub playout: Playout,
+    pub consider_non_term: bool,
+    pub policy_encoding: PolicyEncoding,
+    pub checkpoint_every: f64,
+    pub resume: bool,
+    pub invariant_cache: RefCell<InvariantCache>
 }
 
 impl State{
-    pub const CONSIDER_NON_TERM: bool = true;
-
     pub fn new() -> Self {
         Self {
             adj_mat: DMatrix::from_diagonal_element(1, 1, 0.0),
@@ -31,10 +69,37 @@ impl State{
             size_terminal: 1,
             best_score: f64::NEG_INFINITY,
             seq : Vec::new(),
-            conj: 1
+            conj: 1,
+            mode: GraphMode::AnyGraph,
+            max_edits: 0,
+            connectivity: Connectivity::Ignore,
+            spectral: GraphMatrix::Laplacian,
+            objective: None,
+            aggregation: None,
+            tolerance: None,
+            normalization: Normalization::Raw,
+            degenerate: Degenerate::Fallback,
+            sampler: Sampler::new(),
+            playout: Playout::HeuristicSoftmax,
+            consider_non_term: true,
+            policy_encoding: PolicyEncoding::MoveIdentity,
+            checkpoint_every: 0.0,
+            resume: false,
+            invariant_cache: RefCell::new(InvariantCache::default())
         }
     }
 
+    // same settings (conjecture, mode, ...) with n isolated vertices and an empty sequence
+    pub fn empty_like(& self, n : usize) -> State {
+        let mut st = self.clone();
+        st.adj_mat = DMatrix::zeros(n, n);
+        st.n_sommet = n;
+        st.n_arete = 0;
+        st.seq = Vec::new();
+        st.best_score = f64::NEG_INFINITY;
+        st
+    }
+
     pub fn add_arete(&mut self, from : usize, to : i32) {
         if from as i32 != to && self.n_sommet > from  {
             let mut true_to : usize = 0;
@@ -54,38 +119,90 @@ impl State{
         }
     }
 
+    pub fn remove_arete(&mut self, from : usize, to : usize) {
+        if from < self.n_sommet && to < self.n_sommet && self.adj_mat[(from, to)] != 0.0 {
+            self.n_arete -= 1;
+            self.adj_mat[(from, to)] = 0.0;
+            self.adj_mat[(to, from)] = 0.0;
+        }
+    }
+
+    // only between existing vertices, like remove_arete it does nothing otherwise (add_arete would create a vertex)
+    pub fn toggle_arete(&mut self, from : usize, to : usize) {
+        if from >= self.n_sommet || to >= self.n_sommet {
+            return;
+        }
+        if self.adj_mat[(from, to)] != 0.0 {
+            self.remove_arete(from, to);
+        } else {
+            self.add_arete(from, to as i32);
+        }
+    }
+
+    // the vertices after v are shifted down by one, the graph always keeps at least one vertex
+    pub fn remove_sommet(&mut self, v : usize) {
+        if v < self.n_sommet && self.n_sommet > 1 {
+            let deg = self.adj_mat.row(v).sum() as usize;
+            self.n_arete -= deg;
+            self.n_sommet -= 1;
+            self.adj_mat = self.adj_mat.clone().remove_row(v).remove_column(v);
+        }
+    }
+
     pub fn play(&mut self, m : Move) {
-        self.add_arete(m.from, m.to as i32);
+        match m.kind {
+            MoveKind::AddEdge => self.add_arete(m.from, m.to as i32),
+            MoveKind::RemoveEdge => self.remove_arete(m.from, m.to as usize),
+            MoveKind::ToggleEdge => self.toggle_arete(m.from, m.to as usize),
+            MoveKind::RemoveVertex => self.remove_sommet(m.from)
+        }
         self.seq.push(m);
     }
 
-    pub fn legal_moves(& self) -> Vec<Move> {
-        let mut vec :Vec<Move> = Vec::new();
+    // number of RemoveEdge/RemoveVertex moves still allowed in the sequence, bounded so that playouts end
+    pub fn edits_left(& self) -> usize {
+        let edits = self.seq.iter().filter(|m| m.kind == MoveKind::RemoveEdge || m.kind == MoveKind::RemoveVertex).count();
+        self.max_edits.saturating_sub(edits)
+    }
 
-        //tree mode
-        /*for i in 0..self.n_sommet {
-            let m1 = Move{ind: self.n_sommet, from : i, to : self.n_sommet as i64 };
-            vec.push(m1);
-        }*/
+    pub fn legal_moves(& self) -> Vec<Move> {
+        self.mode.generator().legal_moves(self)
+    }
 
-        //any graph mode
-        if self.n_sommet < self.size_terminal {
-            for i in 0..self.n_sommet {
-                let m1 = Move {ind: self.n_sommet, from: i, to: -1};
-                vec.push(m1);
-            }
+    pub fn repair(&mut self) {
+        if !self.mode.generator().accepts(self) {
+            self.mode.generator().repair(self);
+        }
+        if self.connectivity == Connectivity::Repair && !self.is_connected() {
+            ConnectedMoves.repair(self);
         }
+    }
 
-        for i in 0..self.n_sommet {
-            for j in (i+1)..self.n_sommet {
-                if self.adj_mat[(i, j)] == 0.0 {
-                    let m1 = Move{ind: self.n_sommet, from: i, to: j as i64};
-                    vec.push(m1);
-                }
-            }
+    pub fn n_components(& self) -> usize {
+        let (comp, _, _) = bfs_forest(self);
+        comp.iter().max().map_or(0, |c| c + 1)
+    }
+
+    pub fn is_connected(& self) -> bool {
+        self.n_components() <= 1
+    }
+
+    // the tree searches return the best state met (non-terminal included) or only score terminal states
+    pub fn non_term_report(& self) -> String {
+        if self.consider_non_term {
+            "non-terminal states scored".to_string()
+        } else {
+            "terminal states only".to_string()
         }
+    }
 
-        return vec;
+    pub fn connectivity_report(& self) -> String {
+        let n = self.n_components();
+        if n <= 1 {
+            "connected".to_string()
+        } else {
+            format!("disconnected ({} components)", n)
+        }
     }
 
     pub fn degree_matrix(& self) -> DMatrix<f64> {
@@ -97,14 +214,7 @@ impl State{
     }
 
     pub fn largest_eigenvalue_laplacian_matrix(& self) -> f64 {
-        let deg_mat = self.degree_matrix();
-        let lap_mat = deg_mat - self.adj_mat.clone();
-
-        let mut eigen = SymmetricEigen::new(lap_mat.clone()).eigenvalues;
-        let mut eigenvalues: Vec<_> = eigen.iter().collect();
-        eigenvalues.sort_by(|a: &&f64, b| a.total_cmp(b));
-
-        return *eigenvalues[eigenvalues.len() - 1];
+        self.largest_eigenvalue(GraphMatrix::Laplacian)
     }
 
     pub fn average_degree_neighbors_vec(& self) -> Vec<f64> {
@@ -125,6 +235,7 @@ impl State{
         return mi_vec
     }
 
+    // gain in score of m, NaN when the score before or after is undefined (invalid graph)
     pub fn heuristic(&mut self, m : Move) -> f64{
         let actual_score = self.score();
 
@@ -132,280 +243,255 @@ impl State{
         cl.play(m);
         let new_score = cl.score();
 
-        return new_score - actual_score
+        if !actual_score.is_finite() || !new_score.is_finite() {
+            return f64::NAN;
+        }
+        new_score - actual_score
+    }
+
+    // the bounds were stated for mu, the variants on another matrix get its tag appended (5 -> 5Q)
+    pub fn conjecture_name(& self) -> String {
+        let mut name = self.conj.to_string();
+        if self.spectral != GraphMatrix::Laplacian {
+            name.push_str(self.spectral.tag());
+        }
+        if self.form() != declared_form(self.conj) {
+            let (objective, aggregation) = self.form();
+            name.push_str(&format!("-{}-{}", objective.tag(), aggregation.tag()));
+        }
+        name
+    }
+
+    // objective and aggregation of the conjecture, the fields override what the conjecture declares
+    pub fn form(& self) -> (Objective, Aggregation) {
+        let (objective, aggregation) = declared_form(self.conj);
+        (self.objective.unwrap_or(objective), self.aggregation.unwrap_or(aggregation))
     }
 
+    // score maximized by the searches, the normalized violation
     pub fn score(& self) -> f64 {
-        let mu = self.largest_eigenvalue_laplacian_matrix();
+        let (violation, bound) = self.violation_and_bound();
+        // rejected graphs stay at -inf whatever the normalization
+        if violation == f64::NEG_INFINITY {
+            return violation;
+        }
+        // an undefined score is a rejected graph too, the searches only compare numbers
+        let sc = self.normalization.apply(violation, bound, self.n_sommet);
+        if sc.is_nan() {
+            return f64::NEG_INFINITY;
+        }
+        sc
+    }
+
+    pub fn violation(& self) -> f64 {
+        self.violation_and_bound().0
+    }
+
+    fn violation_and_bound(& self) -> (f64, f64) {
+        if self.connectivity == Connectivity::Reject && !self.is_connected() {
+            return (f64::NEG_INFINITY, f64::NAN);
+        }
+
+        // mu, or the largest eigenvalue of the matrix the bound is checked against
+        let mu = self.largest_eigenvalue(self.spectral);
+        let (objective, _) = self.form();
+        let bound = self.bound();
+        if bound.is_nan() {
+            return (f64::NEG_INFINITY, f64::NAN);
+        }
+        (objective.violation(mu, bound), bound)
+    }
+
+    pub fn tolerance(& self) -> f64 {
+        self.tolerance.unwrap_or(declared_tolerance(self.conj))
+    }
+
+    // the raw violation is tested so that the normalization never changes what counts as a counterexample
+    pub fn is_counterexample(& self) -> bool {
+        self.violation() > self.tolerance()
+    }
+
+    // aggregated bound, NaN if the degenerate policy invalidates the graph
+    pub fn bound(& self) -> f64 {
+        let (_, aggregation) = self.form();
+        match self.degenerate.resolve(&self.bound_terms()) {
+            Some(values) => aggregation.apply(&values),
+            None => f64::NAN
+        }
+    }
+
+    // right-hand side of the conjecture at every vertex or edge
+    pub fn bound_terms(& self) -> Vec<BoundTerm> {
         let deg_mat = self.degree_matrix();
         let avg_deg_neighbors_vec = self.average_degree_neighbors_vec();
 
         let mut results_vec = Vec::new();
         if self.conj == 1 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (4.0*deg_mat[(i, i)].powf(3.0)/avg_deg_neighbors_vec[i]).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (4.0*deg_mat[(i, i)].powf(3.0)/avg_deg_neighbors_vec[i]).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 2 {
             for i in 0..self.n_sommet {
-                let mut partial_result = 2.0*avg_deg_neighbors_vec[i].powf(2.0)/deg_mat[(i, i)];
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = 2.0*avg_deg_neighbors_vec[i].powf(2.0)/deg_mat[(i, i)];
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 3 {
             for i in 0..self.n_sommet {
-                let mut partial_result =  avg_deg_neighbors_vec[i].powf(2.0)/deg_mat[(i, i)] + avg_deg_neighbors_vec[i];
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result =  avg_deg_neighbors_vec[i].powf(2.0)/deg_mat[(i, i)] + avg_deg_neighbors_vec[i];
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 4 {
             for i in 0..self.n_sommet {
-                let mut partial_result =  2.0*deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i];
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result =  2.0*deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i];
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 5 {
             for i in 0..self.n_sommet {
-                let mut partial_result = deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[i];
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[i];
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 6 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (3.0*deg_mat[(i, i)].powf(2.0) + avg_deg_neighbors_vec[i].powf(2.0)).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (3.0*deg_mat[(i, i)].powf(2.0) + avg_deg_neighbors_vec[i].powf(2.0)).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 7 {
             for i in 0..self.n_sommet {
-                let mut partial_result = deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i] + deg_mat[(i, i)];
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i] + deg_mat[(i, i)];
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 8 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (deg_mat[(i, i)]*(avg_deg_neighbors_vec[i] + 3.0*deg_mat[(i, i)])).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (deg_mat[(i, i)]*(avg_deg_neighbors_vec[i] + 3.0*deg_mat[(i, i)])).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 9 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (avg_deg_neighbors_vec[i] + 3.0*deg_mat[(i, i)])/2.0;
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (avg_deg_neighbors_vec[i] + 3.0*deg_mat[(i, i)])/2.0;
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 10 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (deg_mat[(i, i)]*(3.0*avg_deg_neighbors_vec[i] + deg_mat[(i, i)])).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (deg_mat[(i, i)]*(3.0*avg_deg_neighbors_vec[i] + deg_mat[(i, i)])).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 11 {
             for i in 0..self.n_sommet {
-                let mut partial_result = 2.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)].powf(2.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = 2.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)].powf(2.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 12 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (2.0*deg_mat[(i, i)].powf(2.0) + 2.0*avg_deg_neighbors_vec[i].powf(2.0)).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (2.0*deg_mat[(i, i)].powf(2.0) + 2.0*avg_deg_neighbors_vec[i].powf(2.0)).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 13 {
             for i in 0..self.n_sommet {
-                let mut partial_result = 2.0*avg_deg_neighbors_vec[i].powf(4.0)/deg_mat[(i, i)].powf(3.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = 2.0*avg_deg_neighbors_vec[i].powf(4.0)/deg_mat[(i, i)].powf(3.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 14 {
             for i in 0..self.n_sommet {
-                let mut partial_result = 2.0*deg_mat[(i, i)].powf(3.0)/avg_deg_neighbors_vec[i].powf(2.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = 2.0*deg_mat[(i, i)].powf(3.0)/avg_deg_neighbors_vec[i].powf(2.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 15 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (4.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)]).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (4.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)]).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 16 {
             for i in 0..self.n_sommet {
-                let mut partial_result = 2.0*deg_mat[(i, i)].powf(4.0)/avg_deg_neighbors_vec[i].powf(3.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = 2.0*deg_mat[(i, i)].powf(4.0)/avg_deg_neighbors_vec[i].powf(3.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 17 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (5.0*deg_mat[(i, i)].powf(4.0) + 11.0*avg_deg_neighbors_vec[i].powf(4.0)).powf(1.0/4.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (5.0*deg_mat[(i, i)].powf(4.0) + 11.0*avg_deg_neighbors_vec[i].powf(4.0)).powf(1.0/4.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 18 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (2.0*deg_mat[(i, i)].powf(2.0) + 2.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)]).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (2.0*deg_mat[(i, i)].powf(2.0) + 2.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)]).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 19 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (4.0*deg_mat[(i, i)].powf(4.0) + 12.0*avg_deg_neighbors_vec[i].powf(3.0)*deg_mat[(i, i)]).powf(1.0/4.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (4.0*deg_mat[(i, i)].powf(4.0) + 12.0*avg_deg_neighbors_vec[i].powf(3.0)*deg_mat[(i, i)]).powf(1.0/4.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 20 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (7.0*deg_mat[(i, i)].powf(2.0) + 9.0*avg_deg_neighbors_vec[i].powf(2.0)).sqrt()/2.0;
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (7.0*deg_mat[(i, i)].powf(2.0) + 9.0*avg_deg_neighbors_vec[i].powf(2.0)).sqrt()/2.0;
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 21 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (3.0*avg_deg_neighbors_vec[i].powf(2.0) + deg_mat[(i, i)].powf(3.0)/avg_deg_neighbors_vec[i]).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (3.0*avg_deg_neighbors_vec[i].powf(2.0) + deg_mat[(i, i)].powf(3.0)/avg_deg_neighbors_vec[i]).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 22 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (2.0*deg_mat[(i, i)].powf(4.0) + 14.0*avg_deg_neighbors_vec[i].powf(2.0)*deg_mat[(i, i)].powf(2.0)).powf(1.0/4.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (2.0*deg_mat[(i, i)].powf(4.0) + 14.0*avg_deg_neighbors_vec[i].powf(2.0)*deg_mat[(i, i)].powf(2.0)).powf(1.0/4.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 23 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (deg_mat[(i, i)].powf(2.0) + 3.0*avg_deg_neighbors_vec[i]*deg_mat[(i, i)]).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (deg_mat[(i, i)].powf(2.0) + 3.0*avg_deg_neighbors_vec[i]*deg_mat[(i, i)]).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 24 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (6.0*deg_mat[(i, i)].powf(4.0) + 10.0*avg_deg_neighbors_vec[i].powf(4.0)).powf(1.0/4.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (6.0*deg_mat[(i, i)].powf(4.0) + 10.0*avg_deg_neighbors_vec[i].powf(4.0)).powf(1.0/4.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 25 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (3.0*deg_mat[(i, i)].powf(4.0) + 13.0*avg_deg_neighbors_vec[i].powf(2.0)*deg_mat[(i, i)].powf(2.0)).powf(1.0/4.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (3.0*deg_mat[(i, i)].powf(4.0) + 13.0*avg_deg_neighbors_vec[i].powf(2.0)*deg_mat[(i, i)].powf(2.0)).powf(1.0/4.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 26 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (5.0*deg_mat[(i, i)].powf(2.0) + 11.0*avg_deg_neighbors_vec[i]*deg_mat[(i, i)]).sqrt()/2.0;
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (5.0*deg_mat[(i, i)].powf(2.0) + 11.0*avg_deg_neighbors_vec[i]*deg_mat[(i, i)]).sqrt()/2.0;
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 27 {
             for i in 0..self.n_sommet {
-                let mut partial_result = ((3.0*deg_mat[(i, i)].powf(2.0) + 5.0*avg_deg_neighbors_vec[i]*deg_mat[(i, i)])/2.0).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = ((3.0*deg_mat[(i, i)].powf(2.0) + 5.0*avg_deg_neighbors_vec[i]*deg_mat[(i, i)])/2.0).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 28 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (2.0*deg_mat[(i, i)]*avg_deg_neighbors_vec[i] + 2.0*avg_deg_neighbors_vec[i].powf(4.0)/deg_mat[(i, i)].powf(2.0)).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (2.0*deg_mat[(i, i)]*avg_deg_neighbors_vec[i] + 2.0*avg_deg_neighbors_vec[i].powf(4.0)/deg_mat[(i, i)].powf(2.0)).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 29 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (avg_deg_neighbors_vec[i].powf(2.0) + 3.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)]).sqrt();
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (avg_deg_neighbors_vec[i].powf(2.0) + 3.0*avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)]).sqrt();
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 30 {
             for i in 0..self.n_sommet {
-                let mut partial_result = deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)].powf(2.0);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = deg_mat[(i, i)].powf(2.0)/avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[i].powf(3.0)/deg_mat[(i, i)].powf(2.0);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 31 {
             for i in 0..self.n_sommet {
-                let mut partial_result = 4.0*avg_deg_neighbors_vec[i].powf(2.0)/(deg_mat[(i, i)] + avg_deg_neighbors_vec[i]);
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = 4.0*avg_deg_neighbors_vec[i].powf(2.0)/(deg_mat[(i, i)] + avg_deg_neighbors_vec[i]);
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 32 {
             for i in 0..self.n_sommet {
-                let mut partial_result = (avg_deg_neighbors_vec[i].powf(3.0)*(3.0*deg_mat[(i, i)] + avg_deg_neighbors_vec[i])).sqrt()/deg_mat[(i, i)];
-                if partial_result.is_nan() {
-                    partial_result = 0.0;
-                }
-                results_vec.push(partial_result);
+                let partial_result = (avg_deg_neighbors_vec[i].powf(3.0)*(3.0*deg_mat[(i, i)] + avg_deg_neighbors_vec[i])).sqrt()/deg_mat[(i, i)];
+                results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: deg_mat[(i, i)] == 0.0});
             }
         } else if self.conj == 33 {
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (deg_mat[(i, i)] + deg_mat[(j, j)]) - (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = 2.0 * (deg_mat[(i, i)] + deg_mat[(j, j)]) - (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -413,11 +499,8 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) / (deg_mat[(i, i)] + deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = 2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) / (deg_mat[(i, i)] + deg_mat[(j, j)]);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -425,11 +508,8 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) / (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = 2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) / (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -437,11 +517,8 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0)) / (deg_mat[(i, i)] + deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = 2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0)) / (deg_mat[(i, i)] + deg_mat[(j, j)]);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -449,11 +526,8 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))).sqrt();
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -461,11 +535,8 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 + (2.0 * (deg_mat[(i, i)] - 1.0).powf(2.0) + 2.0 * (deg_mat[(j, j)] - 1.0).powf(2.0)).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = 2.0 + (2.0 * (deg_mat[(i, i)] - 1.0).powf(2.0) + 2.0 * (deg_mat[(j, j)] - 1.0).powf(2.0)).sqrt();
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -473,14 +544,11 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 4.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -488,14 +556,11 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * ((avg_deg_neighbors_vec[i] - 1.0).powf(2.0) + (avg_deg_neighbors_vec[j] - 1.0).powf(2.0))
                             + (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -503,16 +568,13 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             - (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 4.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0 - (deg_mat[(i, i)] + deg_mat[(j, j)]) + (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]) ;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0 - (deg_mat[(i, i)] + deg_mat[(j, j)]) + (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]), isolated: false});
                     }
                 }
             }
@@ -520,12 +582,9 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)
+                        let partial_result = (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)
                             + 2.0 * avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -533,15 +592,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (3.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
                             - 2.0 * avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -549,15 +605,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * ((deg_mat[(i, i)] - 1.0).powf(2.0)
                             + (deg_mat[(j, j)] - 1.0).powf(2.0)
                             + avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]
                             - deg_mat[(i, i)] * deg_mat[(j, j)])).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -565,15 +618,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + ((deg_mat[(i, i)] - deg_mat[(j, j)]).powf(2.0)
                             + 2.0 * (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])
                             - 4.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -581,14 +631,11 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 16.0 * (deg_mat[(i, i)] * deg_mat[(j, j)]) / (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -596,13 +643,10 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
+                        let partial_result = (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - (avg_deg_neighbors_vec[i] - avg_deg_neighbors_vec[j]).powf(2.0))
                             / (deg_mat[(i, i)] + deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -610,15 +654,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
+                        let partial_result = 2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             / (2.0
                             + (2.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 4.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt());
-                        if partial_result.is_nan() {
-                            partial_result = deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0);
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0), isolated: false});
                     }
                 }
             }
@@ -626,15 +667,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
                             + (deg_mat[(i, i)] - deg_mat[(j, j)]).powf(2.0)
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -642,16 +680,13 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (
+                        let partial_result = 2.0 * (
                             (deg_mat[(i, i)].powf(2.0)
                                 + deg_mat[(j, j)].powf(2.0)
                                 + avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]
                                 - deg_mat[(i, i)] * deg_mat[(j, j)])
                                 / (deg_mat[(i, i)] + deg_mat[(j, j)]));
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -659,12 +694,9 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
+                        let partial_result = 2.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             - 4.0 * avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] / (deg_mat[(i, i)] + deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -672,16 +704,13 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + ((8.0 * (avg_deg_neighbors_vec[i].powf(4.0) + avg_deg_neighbors_vec[j].powf(4.0))
                             - 8.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             + 4.0).sqrt()
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 6.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -689,16 +718,13 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + ((8.0 * (avg_deg_neighbors_vec[i].powf(4.0) + avg_deg_neighbors_vec[j].powf(4.0))
                             - 8.0 * (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])
                             + 4.0).sqrt()
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 6.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -706,16 +732,13 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
                             + (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])
                             - (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -723,15 +746,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (3.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
                             - (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 4.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -739,12 +759,9 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = ((deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]))
+                        let partial_result = ((deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]))
                             / (2.0 * deg_mat[(i, i)] * deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -752,14 +769,11 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
                             - 8.0 * (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)) / (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -767,15 +781,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] + avg_deg_neighbors_vec[j].powf(2.0))
                             - (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -783,13 +794,10 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] + avg_deg_neighbors_vec[j].powf(2.0))
+                        let partial_result = (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] + avg_deg_neighbors_vec[j].powf(2.0))
                             - (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0)))
                             / (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -797,15 +805,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] + avg_deg_neighbors_vec[j].powf(2.0))
                             - (deg_mat[(i, i)].powf(2.0) + deg_mat[(j, j)].powf(2.0))
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -813,12 +818,9 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
+                        let partial_result = 2.0 * (avg_deg_neighbors_vec[i].powf(2.0) + avg_deg_neighbors_vec[j].powf(2.0))
                             / (2.0 + (2.0 * (deg_mat[(i, i)] - 1.0).powf(2.0) + 2.0 * (deg_mat[(j, j)] - 1.0).powf(2.0)).sqrt());
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -826,17 +828,14 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + (avg_deg_neighbors_vec[i].powf(2.0)
                             + 4.0 * avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]
                             + avg_deg_neighbors_vec[j].powf(2.0)
                             - 2.0 * deg_mat[(i, i)] * deg_mat[(j, j)]
                             - 4.0 * (deg_mat[(i, i)] + deg_mat[(j, j)])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
@@ -844,12 +843,9 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = deg_mat[(i, i)] + deg_mat[(j, j)] + avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]
+                        let partial_result = deg_mat[(i, i)] + deg_mat[(j, j)] + avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]
                             - 4.0 * deg_mat[(i, i)] * deg_mat[(j, j)] / (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -857,11 +853,8 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] * (deg_mat[(i, i)] + deg_mat[(j, j)]) / (deg_mat[(i, i)] * deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        let partial_result = avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j] * (deg_mat[(i, i)] + deg_mat[(j, j)]) / (deg_mat[(i, i)] * deg_mat[(j, j)]);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -869,13 +862,10 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
+                        let partial_result = (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             * (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])
                             / (2.0 * avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -883,15 +873,12 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (avg_deg_neighbors_vec[i].powf(2.0)
+                        let partial_result = (avg_deg_neighbors_vec[i].powf(2.0)
                             + 4.0 * avg_deg_neighbors_vec[i] * avg_deg_neighbors_vec[j]
                             + avg_deg_neighbors_vec[j].powf(2.0)
                             - (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j]))
                             / (deg_mat[(i, i)] + deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -899,13 +886,10 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
+                        let partial_result = (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             * (deg_mat[(i, i)] * avg_deg_neighbors_vec[i] + deg_mat[(j, j)] * avg_deg_neighbors_vec[j])
                             / (2.0 * deg_mat[(i, i)] * deg_mat[(j, j)]);
-                        if partial_result.is_nan() {
-                            partial_result = 0.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 0.0, isolated: false});
                     }
                 }
             }
@@ -913,28 +897,18 @@ impl State{
             for i in 0..self.n_sommet {
                 for j in (i+1)..self.n_sommet {
                     if self.adj_mat[(i, j)] == 1.0 {
-                        let mut partial_result = 2.0
+                        let partial_result = 2.0
                             + ((avg_deg_neighbors_vec[i] - avg_deg_neighbors_vec[j]).powf(2.0)
                             + 4.0 * deg_mat[(i, i)] * deg_mat[(j, j)]
                             - 4.0 * (avg_deg_neighbors_vec[i] + avg_deg_neighbors_vec[j])
                             + 4.0).sqrt();
-                        if partial_result.is_nan() {
-                            partial_result = 2.0;
-                        }
-                        results_vec.push(partial_result);
+                        results_vec.push(BoundTerm{value: partial_result, fallback: 2.0, isolated: false});
                     }
                 }
             }
         }
 
-        let result = if results_vec.is_empty() {
-            0.0
-        } else {
-            *results_vec.iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap()
-        };
-        let sc = mu - result;
-
-        return sc
+        results_vec
     }
 
     pub fn terminal(& self) -> bool {
@@ -964,6 +938,7 @@ pub fn construct_SQ_star(i: usize) -> State {
         let arete_to = arete.1;
         st1.adj_mat[arete] = 1.0;
         st1.adj_mat[(arete_to, arete_from)] = 1.0;
+        st1.n_arete += 1;
     }
 
     st1
@@ -991,6 +966,7 @@ pub fn construct_SQ_17(i: usize) -> State {
         let arete_to = arete.1;
         st1.adj_mat[arete] = 1.0;
         st1.adj_mat[(arete_to, arete_from)] = 1.0;
+        st1.n_arete += 1;
     }
 
     st1
@@ -1018,6 +994,7 @@ pub fn construct_SQ_50(i: usize) -> State {
         let arete_to = arete.1;
         st1.adj_mat[arete] = 1.0;
         st1.adj_mat[(arete_to, arete_from)] = 1.0;
+        st1.n_arete += 1;
     }
 
     st1
@@ -1045,6 +1022,7 @@ pub fn construct_SQ_66(i: usize) -> State {
         let arete_to = arete.1;
         st1.adj_mat[arete] = 1.0;
         st1.adj_mat[(arete_to, arete_from)] = 1.0;
+        st1.n_arete += 1;
     }
 
     st1
@@ -1,2 +1,3 @@
 
 pub(crate) mod conjectures_wagner_1;
+pub(crate) mod objective;
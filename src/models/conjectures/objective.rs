@@ -0,0 +1,234 @@
+// how the per-vertex or per-edge terms of a bound are combined into one value
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Aggregation {
+    Max,
+    Min,
+    Sum,
+    Mean
+}
+
+// which side of the bound is a counterexample, the score is positive exactly on a violation
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Objective {
+    Upper,
+    Lower,
+    UpperRatio,
+    LowerRatio
+}
+
+// rescaling of the violation so that scores of different conjectures and sizes can be compared
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Normalization {
+    Raw,
+    RelativeGap,
+    PerVertex,
+    SizePenalty(f64)
+}
+
+// what to do with a bound term that is not a finite number (isolated vertex, negative radicand, ...)
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Degenerate {
+    Fallback,
+    Skip,
+    ExcludeIsolated,
+    Invalid
+}
+
+// one term of a bound, fallback is the value each conjecture substituted for it before the policy existed
+#[derive(Clone, Copy, Debug)]
+pub struct BoundTerm {
+    pub value: f64,
+    pub fallback: f64,
+    pub isolated: bool
+}
+
+impl Aggregation {
+    pub fn apply(&self, terms: &[f64]) -> f64 {
+        if terms.is_empty() {
+            return 0.0;
+        }
+        match self {
+            Aggregation::Max => terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Min => terms.iter().cloned().fold(f64::INFINITY, f64::min),
+            Aggregation::Sum => terms.iter().sum(),
+            Aggregation::Mean => terms.iter().sum::<f64>() / terms.len() as f64
+        }
+    }
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Aggregation::Max => "max",
+            Aggregation::Min => "min",
+            Aggregation::Sum => "sum",
+            Aggregation::Mean => "mean"
+        }
+    }
+}
+
+impl Objective {
+    // value is the spectral quantity (mu by default), bound the aggregated right-hand side;
+    // a ratio to a zero or infinite bound is undefined and the graph is rejected (-inf)
+    pub fn violation(&self, value: f64, bound: f64) -> f64 {
+        let ratio = matches!(self, Objective::UpperRatio | Objective::LowerRatio);
+        if ratio && (bound == 0.0 || !bound.is_finite()) {
+            return f64::NEG_INFINITY;
+        }
+        match self {
+            Objective::Upper => value - bound,
+            Objective::Lower => bound - value,
+            Objective::UpperRatio => value / bound - 1.0,
+            Objective::LowerRatio => 1.0 - value / bound
+        }
+    }
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Objective::Upper => "upper",
+            Objective::Lower => "lower",
+            Objective::UpperRatio => "upper-ratio",
+            Objective::LowerRatio => "lower-ratio"
+        }
+    }
+}
+
+impl Degenerate {
+    // the terms to aggregate, None if the graph is invalid for the conjecture
+    pub fn resolve(&self, terms: &[BoundTerm]) -> Option<Vec<f64>> {
+        let mut values = Vec::new();
+        for t in terms {
+            if t.value.is_finite() {
+                values.push(t.value);
+                continue;
+            }
+            match self {
+                Degenerate::Fallback => values.push(if t.value.is_nan() { t.fallback } else { t.value }),
+                Degenerate::Skip => {},
+                Degenerate::ExcludeIsolated => {
+                    if !t.isolated {
+                        return None;
+                    }
+                },
+                Degenerate::Invalid => return None
+            }
+        }
+        // a bound with every term undefined says nothing about the graph
+        if values.is_empty() && !terms.is_empty() {
+            return None;
+        }
+        Some(values)
+    }
+}
+
+impl Normalization {
+    // a gap relative to a zero or infinite bound (edgeless graph of an edge conjecture, ...) is undefined, the graph is rejected (-inf)
+    pub fn apply(&self, violation: f64, bound: f64, n_sommet: usize) -> f64 {
+        match self {
+            Normalization::Raw => violation,
+            Normalization::RelativeGap => {
+                if bound == 0.0 || !bound.is_finite() {
+                    return f64::NEG_INFINITY;
+                }
+                violation / bound.abs()
+            },
+            Normalization::PerVertex => violation / n_sommet as f64,
+            Normalization::SizePenalty(lambda) => violation - lambda * n_sommet as f64
+        }
+    }
+}
+
+// form declared by each conjecture of the catalogue, the 68 of Wagner are upper bounds taken as a max over vertices or edges
+pub fn declared_form(_conj: usize) -> (Objective, Aggregation) {
+    (Objective::Upper, Aggregation::Max)
+}
+
+// violation above which a graph is reported as a counterexample
+pub fn declared_tolerance(_conj: usize) -> f64 {
+    0.0001
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::conjectures::conjectures_wagner_1::State;
+
+    #[test]
+    fn violation_sides() {
+        assert_eq!(Objective::Upper.violation(3.0, 2.0), 1.0);
+        assert_eq!(Objective::Lower.violation(3.0, 2.0), -1.0);
+        assert_eq!(Objective::UpperRatio.violation(3.0, 2.0), 0.5);
+        assert_eq!(Objective::LowerRatio.violation(1.0, 2.0), 0.5);
+    }
+
+    #[test]
+    fn ratio_to_a_zero_bound_is_rejected() {
+        for objective in [Objective::UpperRatio, Objective::LowerRatio] {
+            assert_eq!(objective.violation(0.0, 0.0), f64::NEG_INFINITY);
+            assert_eq!(objective.violation(1.0, 0.0), f64::NEG_INFINITY);
+            assert_eq!(objective.violation(1.0, f64::INFINITY), f64::NEG_INFINITY);
+            assert_eq!(objective.violation(1.0, f64::NAN), f64::NEG_INFINITY);
+        }
+        assert_eq!(Objective::Upper.violation(1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn aggregations() {
+        let terms = [1.0, 4.0, -2.0, 5.0];
+        assert_eq!(Aggregation::Max.apply(&terms), 5.0);
+        assert_eq!(Aggregation::Min.apply(&terms), -2.0);
+        assert_eq!(Aggregation::Sum.apply(&terms), 8.0);
+        assert_eq!(Aggregation::Mean.apply(&terms), 2.0);
+        // an empty bound (edge conjecture on an edgeless graph) is 0, as before the aggregations existed
+        for aggregation in [Aggregation::Max, Aggregation::Min, Aggregation::Sum, Aggregation::Mean] {
+            assert_eq!(aggregation.apply(&[]), 0.0);
+        }
+    }
+
+    #[test]
+    fn normalizations_on_zero_and_empty_bounds() {
+        assert_eq!(Normalization::Raw.apply(1.0, 0.0, 4), 1.0);
+        assert_eq!(Normalization::RelativeGap.apply(1.0, -2.0, 4), 0.5);
+        assert_eq!(Normalization::RelativeGap.apply(0.0, 0.0, 4), f64::NEG_INFINITY);
+        assert_eq!(Normalization::RelativeGap.apply(1.0, f64::INFINITY, 4), f64::NEG_INFINITY);
+        assert_eq!(Normalization::RelativeGap.apply(1.0, Aggregation::Max.apply(&[]), 4), f64::NEG_INFINITY);
+        assert_eq!(Normalization::PerVertex.apply(1.0, 0.0, 4), 0.25);
+        assert_eq!(Normalization::SizePenalty(0.5).apply(1.0, 0.0, 4), -1.0);
+    }
+
+    #[test]
+    fn degenerate_terms() {
+        let terms = [BoundTerm{value: 2.0, fallback: 0.0, isolated: false},
+                     BoundTerm{value: f64::NAN, fallback: 1.0, isolated: true}];
+        assert_eq!(Degenerate::Fallback.resolve(&terms), Some(vec![2.0, 1.0]));
+        assert_eq!(Degenerate::Skip.resolve(&terms), Some(vec![2.0]));
+        assert_eq!(Degenerate::ExcludeIsolated.resolve(&terms), Some(vec![2.0]));
+        assert_eq!(Degenerate::Invalid.resolve(&terms), None);
+        assert_eq!(Degenerate::Skip.resolve(&terms[1..]), None);
+        assert_eq!(Degenerate::Skip.resolve(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn edgeless_root_is_never_nan() {
+        for conj in 1..69 {
+            for normalization in [Normalization::Raw, Normalization::RelativeGap, Normalization::PerVertex] {
+                let mut st = State::new();
+                st.conj = conj;
+                st.normalization = normalization;
+                assert!(!st.score().is_nan(), "conjecture {} {:?}", conj, normalization);
+                let mut pair = st.empty_like(2);
+                pair.add_arete(0, 1);
+                assert!(!pair.score().is_nan(), "conjecture {} {:?}", conj, normalization);
+            }
+        }
+    }
+
+    #[test]
+    fn default_keeps_the_fallback_values() {
+        // the one-vertex root scores mu - 0 with the substitute values, it was rejected when skipping them
+        let st = State::new();
+        assert_eq!(st.degenerate, Degenerate::Fallback);
+        assert_eq!(st.score(), 0.0);
+        let mut skip = State::new();
+        skip.degenerate = Degenerate::Skip;
+        assert_eq!(skip.score(), f64::NEG_INFINITY);
+    }
+}
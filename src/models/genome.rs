@@ -0,0 +1,90 @@
+use crate::models::conjectures::conjectures_wagner_1::State;
+
+// vector of the n(n-1)/2 entries above the diagonal of the adjacency matrix (row by row), the genome of the continuous optimizers;
+// encode and decode are exact inverses on graphs of n vertices, a coordinate above the threshold is an edge
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Genome {
+    pub n: usize,
+    pub threshold: f64
+}
+
+impl Genome {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            threshold: 0.5
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.n*self.n.saturating_sub(1)/2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // edge of each coordinate, in the order of the genome
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for i in 0..self.n {
+            for j in (i+1)..self.n {
+                edges.push((i, j));
+            }
+        }
+        edges
+    }
+
+    // 1.0 for an edge, 0.0 otherwise, only the first n vertices of st are read
+    pub fn encode(&self, st: &State) -> Vec<f64> {
+        self.edges().iter().map(|&(i, j)| {
+            if i < st.n_sommet && j < st.n_sommet && st.adj_mat[(i, j)] > 0.0 { 1.0 } else { 0.0 }
+        }).collect()
+    }
+
+    // graph of n vertices with the settings of init_st, repaired to its graph class
+    pub fn decode(&self, x: &[f64], init_st: &State) -> State {
+        let mut st = init_st.empty_like(self.n);
+        for (k, (i, j)) in self.edges().into_iter().enumerate() {
+            if x[k] > self.threshold {
+                st.adj_mat[(i, j)] = 1.0;
+                st.adj_mat[(j, i)] = 1.0;
+                st.n_arete += 1;
+            }
+        }
+        st.repair();
+        st
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_inverts_encode() {
+        let mut st = State::new().empty_like(6);
+        for &(i, j) in &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 0), (0, 3)] {
+            st.add_arete(i, j);
+        }
+        let genome = Genome::new(6);
+        let x = genome.encode(&st);
+        assert_eq!(x.len(), genome.len());
+        assert_eq!(x.iter().sum::<f64>(), 7.0);
+
+        let back = genome.decode(&x, &st);
+        assert_eq!(back.adj_mat, st.adj_mat);
+        assert_eq!(back.n_arete, st.n_arete);
+        assert_eq!(genome.encode(&back), x);
+    }
+
+    #[test]
+    fn decode_thresholds_the_coordinates() {
+        let genome = Genome::new(3);
+        // coordinates of (0, 1), (0, 2) and (1, 2)
+        let st = genome.decode(&[0.9, 0.5, 0.2], &State::new());
+        assert_eq!(st.n_sommet, 3);
+        assert_eq!(genome.encode(&st), vec![1.0, 0.0, 0.0]);
+        assert!(Genome::new(1).is_empty());
+    }
+}
@@ -0,0 +1,699 @@
+use std::cell::RefMut;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use nalgebra::DMatrix;
+use crate::models::conjectures::conjectures_wagner_1::State;
+use crate::models::spectral::GraphMatrix;
+
+// invariants that conjecture definitions can reference, undefined values (diameter of a disconnected
+// graph, girth of a forest) are f64::INFINITY; the exact searches (clique, independence and domination numbers)
+// are limited to 128 vertices and give f64::NAN above
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Invariant {
+    MinDegree,
+    MaxDegree,
+    AverageDegree,
+    Diameter,
+    Radius,
+    Girth,
+    IndependenceNumber,
+    MatchingNumber,
+    CliqueNumber,
+    DominationNumber,
+    Proximity,
+    Remoteness,
+    AlgebraicConnectivity,
+    ChromaticLowerBound,
+    ChromaticUpperBound
+}
+
+// values already computed for the graph whose adjacency hashes to key
+#[derive(Clone, Default)]
+pub struct InvariantCache {
+    key: u64,
+    pub values: HashMap<Invariant, f64>,
+    pub spectra: HashMap<GraphMatrix, Vec<f64>>,
+    pub distances: Option<DMatrix<f64>>
+}
+
+// bitsets of the neighbours, None above 128 vertices
+fn neighbourhoods(st: &State) -> Option<Vec<u128>> {
+    if st.n_sommet > 128 {
+        return None;
+    }
+    let mut nb = vec![0u128; st.n_sommet];
+    for (i, row) in nb.iter_mut().enumerate() {
+        for j in 0..st.n_sommet {
+            if i != j && st.adj_mat[(i, j)] == 1.0 {
+                *row |= 1 << j;
+            }
+        }
+    }
+    Some(nb)
+}
+
+fn all_vertices(n: usize) -> u128 {
+    if n == 128 { u128::MAX } else { (1u128 << n) - 1 }
+}
+
+// Bron-Kerbosch with pivoting, size of the largest clique of the graph given by nb
+fn max_clique(nb: &[u128], r: u32, mut p: u128, mut x: u128, best: &mut u32) {
+    if p == 0 && x == 0 {
+        *best = (*best).max(r);
+        return;
+    }
+    if r + p.count_ones() <= *best {
+        return;
+    }
+
+    let pivot = (p | x).trailing_zeros() as usize;
+    let mut candidates = p & !nb[pivot];
+    while candidates != 0 {
+        let v = candidates.trailing_zeros() as usize;
+        candidates &= candidates - 1;
+        max_clique(nb, r + 1, p & nb[v], x & nb[v], best);
+        p &= !(1 << v);
+        x |= 1 << v;
+    }
+}
+
+// true if the vertices not yet dominated can be dominated with k more vertices
+fn dominates(closed_nb: &[u128], all: u128, dominated: u128, k: usize) -> bool {
+    if dominated == all {
+        return true;
+    }
+    if k == 0 {
+        return false;
+    }
+
+    // one of the vertices of N[v] must be chosen for the first undominated v
+    let v = (!dominated & all).trailing_zeros() as usize;
+    let mut candidates = closed_nb[v];
+    while candidates != 0 {
+        let u = candidates.trailing_zeros() as usize;
+        candidates &= candidates - 1;
+        if dominates(closed_nb, all, dominated | closed_nb[u], k - 1) {
+            return true;
+        }
+    }
+    false
+}
+
+fn blossom_lca(a: usize, b: usize, mate: &[usize], base: &[usize], parent: &[usize]) -> usize {
+    let mut used = vec![false; mate.len()];
+    let mut a = a;
+    loop {
+        a = base[a];
+        used[a] = true;
+        if mate[a] == usize::MAX {
+            break;
+        }
+        a = parent[mate[a]];
+    }
+    let mut b = b;
+    loop {
+        b = base[b];
+        if used[b] {
+            return b;
+        }
+        b = parent[mate[b]];
+    }
+}
+
+fn blossom_mark_path(v: usize, b: usize, child: usize, mate: &[usize], base: &[usize], blossom: &mut [bool], parent: &mut [usize]) {
+    let mut v = v;
+    let mut child = child;
+    while base[v] != b {
+        blossom[base[v]] = true;
+        blossom[base[mate[v]]] = true;
+        parent[v] = child;
+        child = mate[v];
+        v = parent[mate[v]];
+    }
+}
+
+// Edmonds' blossom algorithm, size of a maximum matching
+fn maximum_matching(adj: &[Vec<usize>]) -> usize {
+    let n = adj.len();
+    let none = usize::MAX;
+    let mut mate = vec![none; n];
+
+    let mut result = 0;
+    for root in 0..n {
+        if mate[root] != none {
+            continue;
+        }
+
+        let mut used = vec![false; n];
+        let mut parent = vec![none; n];
+        let mut base: Vec<usize> = (0..n).collect();
+        let mut queue = VecDeque::new();
+        used[root] = true;
+        queue.push_back(root);
+
+        let mut end = none;
+        'search: while let Some(v) = queue.pop_front() {
+            for &to in &adj[v] {
+                if base[v] == base[to] || mate[v] == to {
+                    continue;
+                }
+                if to == root || (mate[to] != none && parent[mate[to]] != none) {
+                    let cur_base = blossom_lca(v, to, &mate, &base, &parent);
+                    let mut blossom = vec![false; n];
+                    blossom_mark_path(v, cur_base, to, &mate, &base, &mut blossom, &mut parent);
+                    blossom_mark_path(to, cur_base, v, &mate, &base, &mut blossom, &mut parent);
+                    for i in 0..n {
+                        if blossom[base[i]] {
+                            base[i] = cur_base;
+                            if !used[i] {
+                                used[i] = true;
+                                queue.push_back(i);
+                            }
+                        }
+                    }
+                } else if parent[to] == none {
+                    parent[to] = v;
+                    if mate[to] == none {
+                        end = to;
+                        break 'search;
+                    }
+                    used[mate[to]] = true;
+                    queue.push_back(mate[to]);
+                }
+            }
+        }
+
+        // augment along the path found
+        let mut v = end;
+        while v != none {
+            let pv = parent[v];
+            let ppv = mate[pv];
+            mate[v] = pv;
+            mate[pv] = v;
+            v = ppv;
+        }
+        if end != none {
+            result += 1;
+        }
+    }
+
+    result
+}
+
+// maps v and the next vertices of a onto unused vertices of b, keeping the colours and the edges to the vertices already mapped
+fn extend_isomorphism(a: &State, b: &State, ca: &[u64], cb: &[u64], v: usize, map: &mut [usize], used: &mut [bool]) -> bool {
+    if v == a.n_sommet {
+        return true
+    }
+    for w in 0..b.n_sommet {
+        if used[w] || ca[v] != cb[w] {
+            continue
+        }
+        if (0..v).any(|u| (a.adj_mat[(u, v)] == 1.0) != (b.adj_mat[(map[u], w)] == 1.0)) {
+            continue
+        }
+        map[v] = w;
+        used[w] = true;
+        if extend_isomorphism(a, b, ca, cb, v + 1, map, used) {
+            return true
+        }
+        used[w] = false;
+    }
+    false
+}
+
+impl State {
+    // hash of the labelled graph
+    pub fn adjacency_key(& self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.n_sommet.hash(&mut hasher);
+        for i in 0..self.n_sommet {
+            for j in (i+1)..self.n_sommet {
+                (self.adj_mat[(i, j)] == 1.0).hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    // colours of the vertices after a Weisfeiler-Lehman refinement started from the degrees,
+    // an isomorphism maps a vertex to one of the same colour
+    fn refined_colours(& self) -> Vec<u64> {
+        let n = self.n_sommet;
+        let distinct = |c: &[u64]| c.iter().collect::<HashSet<_>>().len();
+        let mut colours: Vec<u64> = self.degrees().iter().map(|&d| d as u64).collect();
+        for _ in 0..n {
+            let mut next = Vec::with_capacity(n);
+            for v in 0..n {
+                let mut nb: Vec<u64> = (0..n).filter(|&w| self.adj_mat[(v, w)] == 1.0).map(|w| colours[w]).collect();
+                nb.sort_unstable();
+                let mut hasher = DefaultHasher::new();
+                colours[v].hash(&mut hasher);
+                nb.hash(&mut hasher);
+                next.push(hasher.finish());
+            }
+            // the partition of the vertices no longer gets finer
+            let stable = distinct(&next) == distinct(&colours);
+            colours = next;
+            if stable {
+                break
+            }
+        }
+        colours
+    }
+
+    // n then the upper triangle of the adjacency matrix row by row, one bit per pair, the compact form of the graphs kept by BFS
+    pub fn edge_bits(& self) -> Vec<u64> {
+        let n = self.n_sommet;
+        let mut bits = vec![n as u64];
+        let mut k = 0;
+        for i in 0..n {
+            for j in (i+1)..n {
+                if k % 64 == 0 {
+                    bits.push(0);
+                }
+                if self.adj_mat[(i, j)] == 1.0 {
+                    *bits.last_mut().unwrap() |= 1 << (k % 64);
+                }
+                k += 1;
+            }
+        }
+        bits
+    }
+
+    // graph of edge_bits with the settings of self
+    pub fn with_edge_bits(& self, bits: &[u64]) -> State {
+        let n = bits[0] as usize;
+        let mut st = self.empty_like(n);
+        let mut k = 0;
+        for i in 0..n {
+            for j in (i+1)..n {
+                if bits[1 + k/64] >> (k % 64) & 1 == 1 {
+                    st.adj_mat[(i, j)] = 1.0;
+                    st.adj_mat[(j, i)] = 1.0;
+                    st.n_arete += 1;
+                }
+                k += 1;
+            }
+        }
+        st
+    }
+
+    // same for isomorphic graphs: hash of the refined colours,
+    // the graphs the refinement cannot tell apart (e.g. all the d-regular graphs of an order) share it too
+    pub fn isomorphism_key(& self) -> u64 {
+        let mut colours = self.refined_colours();
+        colours.sort_unstable();
+
+        let mut hasher = DefaultHasher::new();
+        self.n_sommet.hash(&mut hasher);
+        colours.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // exact test, by backtracking on the vertices of self mapped to those of other with the same refined colour
+    pub fn isomorphic(& self, other: &State) -> bool {
+        let n = self.n_sommet;
+        if other.n_sommet != n {
+            return false
+        }
+        let (a, b) = (self.refined_colours(), other.refined_colours());
+        let (mut sa, mut sb) = (a.clone(), b.clone());
+        sa.sort_unstable();
+        sb.sort_unstable();
+        if sa != sb {
+            return false
+        }
+        let mut map = vec![usize::MAX; n];
+        let mut used = vec![false; n];
+        extend_isomorphism(self, other, &a, &b, 0, &mut map, &mut used)
+    }
+
+    // the cache is dropped as soon as adj_mat changes
+    pub fn cache(& self) -> RefMut<'_, InvariantCache> {
+        let key = self.adjacency_key();
+        let mut cache = self.invariant_cache.borrow_mut();
+        if cache.key != key {
+            cache.key = key;
+            cache.values.clear();
+            cache.spectra.clear();
+            cache.distances = None;
+        }
+        cache
+    }
+
+    // value of inv, computed once per graph
+    pub fn invariant(& self, inv : Invariant) -> f64 {
+        if let Some(&v) = self.cache().values.get(&inv) {
+            return v;
+        }
+
+        let v = self.compute_invariant(inv);
+        self.cache().values.insert(inv, v);
+        v
+    }
+
+    // shortest path lengths, f64::INFINITY between vertices of different components, computed once per graph
+    pub fn distance_matrix(& self) -> DMatrix<f64> {
+        if let Some(dist) = &self.cache().distances {
+            return dist.clone();
+        }
+
+        let dist = self.compute_distance_matrix();
+        self.cache().distances = Some(dist.clone());
+        dist
+    }
+
+    fn compute_distance_matrix(& self) -> DMatrix<f64> {
+        let n = self.n_sommet;
+        let mut dist = DMatrix::from_element(n, n, f64::INFINITY);
+        for s in 0..n {
+            dist[(s, s)] = 0.0;
+            let mut queue = VecDeque::new();
+            queue.push_back(s);
+            while let Some(v) = queue.pop_front() {
+                for w in 0..n {
+                    if self.adj_mat[(v, w)] == 1.0 && dist[(s, w)] == f64::INFINITY {
+                        dist[(s, w)] = dist[(s, v)] + 1.0;
+                        queue.push_back(w);
+                    }
+                }
+            }
+        }
+        dist
+    }
+
+    pub fn degrees(& self) -> Vec<f64> {
+        (0..self.n_sommet).map(|i| self.adj_mat.row(i).sum()).collect()
+    }
+
+    pub fn diameter(& self) -> f64 {
+        self.invariant(Invariant::Diameter)
+    }
+
+    pub fn radius(& self) -> f64 {
+        self.invariant(Invariant::Radius)
+    }
+
+    pub fn girth(& self) -> f64 {
+        self.invariant(Invariant::Girth)
+    }
+
+    pub fn independence_number(& self) -> f64 {
+        self.invariant(Invariant::IndependenceNumber)
+    }
+
+    pub fn matching_number(& self) -> f64 {
+        self.invariant(Invariant::MatchingNumber)
+    }
+
+    pub fn clique_number(& self) -> f64 {
+        self.invariant(Invariant::CliqueNumber)
+    }
+
+    pub fn domination_number(& self) -> f64 {
+        self.invariant(Invariant::DominationNumber)
+    }
+
+    pub fn proximity(& self) -> f64 {
+        self.invariant(Invariant::Proximity)
+    }
+
+    pub fn remoteness(& self) -> f64 {
+        self.invariant(Invariant::Remoteness)
+    }
+
+    pub fn algebraic_connectivity(& self) -> f64 {
+        self.invariant(Invariant::AlgebraicConnectivity)
+    }
+
+    fn compute_invariant(& self, inv : Invariant) -> f64 {
+        let n = self.n_sommet;
+        match inv {
+            Invariant::MinDegree => self.degrees().into_iter().fold(f64::INFINITY, f64::min),
+            Invariant::MaxDegree => self.degrees().into_iter().fold(0.0, f64::max),
+            Invariant::AverageDegree => self.degrees().iter().sum::<f64>() / n as f64,
+            Invariant::Diameter | Invariant::Radius => {
+                let dist = self.distance_matrix();
+                let ecc: Vec<f64> = (0..n).map(|i| dist.row(i).max()).collect();
+                if inv == Invariant::Diameter {
+                    ecc.into_iter().fold(0.0, f64::max)
+                } else {
+                    ecc.into_iter().fold(f64::INFINITY, f64::min)
+                }
+            },
+            Invariant::Proximity | Invariant::Remoteness => {
+                // extreme average distance from a vertex to the others
+                if n < 2 {
+                    return 0.0;
+                }
+                let dist = self.distance_matrix();
+                let avg: Vec<f64> = (0..n).map(|i| dist.row(i).sum() / (n - 1) as f64).collect();
+                if inv == Invariant::Proximity {
+                    avg.into_iter().fold(f64::INFINITY, f64::min)
+                } else {
+                    avg.into_iter().fold(0.0, f64::max)
+                }
+            },
+            Invariant::Girth => {
+                // shortest cycle through a non-tree edge of a BFS from every vertex
+                let mut girth = f64::INFINITY;
+                for s in 0..n {
+                    let mut dist = vec![usize::MAX; n];
+                    let mut parent = vec![usize::MAX; n];
+                    dist[s] = 0;
+                    let mut queue = VecDeque::new();
+                    queue.push_back(s);
+                    while let Some(v) = queue.pop_front() {
+                        for w in 0..n {
+                            if self.adj_mat[(v, w)] != 1.0 || w == v {
+                                continue;
+                            }
+                            if dist[w] == usize::MAX {
+                                dist[w] = dist[v] + 1;
+                                parent[w] = v;
+                                queue.push_back(w);
+                            } else if parent[v] != w {
+                                girth = girth.min((dist[v] + dist[w] + 1) as f64);
+                            }
+                        }
+                    }
+                }
+                girth
+            },
+            Invariant::CliqueNumber | Invariant::IndependenceNumber => {
+                // independent sets are the cliques of the complement
+                let Some(mut nb) = neighbourhoods(self) else {
+                    return f64::NAN;
+                };
+                let all = all_vertices(n);
+                if inv == Invariant::IndependenceNumber {
+                    for (i, row) in nb.iter_mut().enumerate() {
+                        *row = !*row & all & !(1 << i);
+                    }
+                }
+                let mut best = 0;
+                max_clique(&nb, 0, all, 0, &mut best);
+                best as f64
+            },
+            Invariant::DominationNumber => {
+                let Some(nb) = neighbourhoods(self) else {
+                    return f64::NAN;
+                };
+                let closed_nb: Vec<u128> = nb.iter().enumerate().map(|(i, &row)| row | (1 << i)).collect();
+                let all = all_vertices(n);
+                let mut k = 0;
+                while !dominates(&closed_nb, all, 0, k) {
+                    k += 1;
+                }
+                k as f64
+            },
+            Invariant::MatchingNumber => {
+                let adj: Vec<Vec<usize>> = (0..n).map(|i| (0..n).filter(|&j| j != i && self.adj_mat[(i, j)] == 1.0).collect()).collect();
+                maximum_matching(&adj) as f64
+            },
+            Invariant::AlgebraicConnectivity => {
+                if n < 2 {
+                    return 0.0;
+                }
+                self.spectrum(GraphMatrix::Laplacian)[1]
+            },
+            Invariant::ChromaticLowerBound => {
+                // chi >= omega and chi >= n / alpha
+                let alpha = self.independence_number();
+                if alpha == 0.0 {
+                    return 0.0;
+                }
+                self.clique_number().max((n as f64 / alpha).ceil())
+            },
+            Invariant::ChromaticUpperBound => {
+                // greedy colouring in non-increasing degree order (Welsh-Powell)
+                let degrees = self.degrees();
+                let mut order: Vec<usize> = (0..n).collect();
+                order.sort_by(|&a, &b| degrees[b].total_cmp(&degrees[a]));
+                let mut colour = vec![usize::MAX; n];
+                let mut n_colours = 0;
+                for &v in &order {
+                    let mut c = 0;
+                    while (0..n).any(|w| self.adj_mat[(v, w)] == 1.0 && w != v && colour[w] == c) {
+                        c += 1;
+                    }
+                    colour[v] = c;
+                    n_colours = n_colours.max(c + 1);
+                }
+                n_colours as f64
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(n: usize, edges: &[(usize, usize)]) -> State {
+        let mut st = State::new().empty_like(n);
+        for &(i, j) in edges {
+            st.add_arete(i, j as i32);
+        }
+        st
+    }
+
+    fn cycle(n: usize) -> State {
+        let edges: Vec<(usize, usize)> = (0..n).map(|i| (i, (i + 1) % n)).collect();
+        graph(n, &edges)
+    }
+
+    fn petersen() -> State {
+        let mut edges = Vec::new();
+        for i in 0..5 {
+            edges.push((i, (i + 1) % 5));
+            edges.push((i, i + 5));
+            edges.push((i + 5, (i + 2) % 5 + 5));
+        }
+        graph(10, &edges)
+    }
+
+    fn k33() -> State {
+        let mut edges = Vec::new();
+        for i in 0..3 {
+            for j in 3..6 {
+                edges.push((i, j));
+            }
+        }
+        graph(6, &edges)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn petersen_invariants() {
+        let st = petersen();
+        assert_eq!(st.n_arete, 15);
+        assert_eq!(st.invariant(Invariant::MinDegree), 3.0);
+        assert_eq!(st.invariant(Invariant::MaxDegree), 3.0);
+        assert_eq!(st.diameter(), 2.0);
+        assert_eq!(st.radius(), 2.0);
+        assert_eq!(st.girth(), 5.0);
+        assert_eq!(st.independence_number(), 4.0);
+        assert_eq!(st.matching_number(), 5.0);
+        assert_eq!(st.clique_number(), 2.0);
+        assert_eq!(st.domination_number(), 3.0);
+        assert!(close(st.proximity(), 15.0 / 9.0));
+        assert!(close(st.remoteness(), 15.0 / 9.0));
+        assert!(close(st.algebraic_connectivity(), 2.0));
+        assert_eq!(st.invariant(Invariant::ChromaticLowerBound), 3.0);
+        assert!(st.invariant(Invariant::ChromaticUpperBound) >= 3.0);
+    }
+
+    #[test]
+    fn cycle_invariants() {
+        let st = cycle(5);
+        assert_eq!(st.diameter(), 2.0);
+        assert_eq!(st.girth(), 5.0);
+        assert_eq!(st.independence_number(), 2.0);
+        assert_eq!(st.matching_number(), 2.0);
+        assert_eq!(st.clique_number(), 2.0);
+        assert_eq!(st.domination_number(), 2.0);
+        assert!(close(st.algebraic_connectivity(), 2.0 - 2.0 * (2.0 * std::f64::consts::PI / 5.0).cos()));
+        assert_eq!(st.invariant(Invariant::ChromaticLowerBound), 3.0);
+        assert_eq!(st.invariant(Invariant::ChromaticUpperBound), 3.0);
+    }
+
+    #[test]
+    fn complete_bipartite_invariants() {
+        let st = k33();
+        assert_eq!(st.diameter(), 2.0);
+        assert_eq!(st.girth(), 4.0);
+        assert_eq!(st.independence_number(), 3.0);
+        assert_eq!(st.matching_number(), 3.0);
+        assert_eq!(st.clique_number(), 2.0);
+        assert_eq!(st.domination_number(), 2.0);
+        assert!(close(st.algebraic_connectivity(), 3.0));
+        assert_eq!(st.invariant(Invariant::ChromaticUpperBound), 2.0);
+    }
+
+    #[test]
+    fn undefined_invariants() {
+        // a path has no cycle, two components have no finite diameter
+        let path = graph(4, &[(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(path.girth(), f64::INFINITY);
+        assert_eq!(path.diameter(), 3.0);
+        assert_eq!(path.matching_number(), 2.0);
+        let two = graph(4, &[(0, 1), (2, 3)]);
+        assert_eq!(two.diameter(), f64::INFINITY);
+
+        let large = cycle(130);
+        assert!(large.clique_number().is_nan());
+        assert!(large.domination_number().is_nan());
+        assert_eq!(large.girth(), 130.0);
+    }
+
+    #[test]
+    fn cache_follows_the_graph() {
+        let mut st = cycle(5);
+        assert_eq!(st.girth(), 5.0);
+        assert_eq!(st.distance_matrix()[(0, 2)], 2.0);
+        st.add_arete(0, 2);
+        assert_eq!(st.girth(), 3.0);
+        assert_eq!(st.distance_matrix()[(0, 2)], 1.0);
+    }
+
+    #[test]
+    fn isomorphism_key_ignores_labels() {
+        let a = graph(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (1, 3)]);
+        let b = graph(5, &[(4, 3), (3, 2), (2, 1), (1, 0), (3, 1)]);
+        let c = graph(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (0, 2)]);
+        assert_eq!(a.isomorphism_key(), b.isomorphism_key());
+        assert_ne!(a.adjacency_key(), c.adjacency_key());
+        assert_ne!(a.isomorphism_key(), c.isomorphism_key());
+    }
+
+    #[test]
+    fn regular_graphs_share_the_key_but_not_isomorphism() {
+        // K3,3 and the prism are both 3-regular on 6 vertices
+        let prism = graph(6, &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (0, 3), (1, 4), (2, 5)]);
+        let k33 = k33();
+        assert_eq!(prism.isomorphism_key(), k33.isomorphism_key());
+        assert!(!prism.isomorphic(&k33));
+        assert!(petersen().isomorphic(&petersen()));
+
+        let relabelled = graph(6, &[(5, 4), (4, 3), (3, 5), (2, 1), (1, 0), (0, 2), (5, 2), (4, 1), (3, 0)]);
+        assert!(prism.isomorphic(&relabelled));
+        assert!(!prism.isomorphic(&cycle(6)));
+    }
+
+    #[test]
+    fn edge_bits_round_trip() {
+        let st = petersen();
+        let bits = st.edge_bits();
+        assert_eq!(bits.len(), 2);
+        let back = st.with_edge_bits(&bits);
+        assert_eq!(back.adj_mat, st.adj_mat);
+        assert_eq!(back.n_arete, 15);
+        let large = cycle(20);
+        assert_eq!(large.with_edge_bits(&large.edge_bits()).adj_mat, large.adj_mat);
+    }
+}
@@ -1,2 +1,8 @@
 pub(crate) mod conjectures;
-
+pub(crate) mod moveGenerator;
+// library for the conjecture definitions, not every invariant is used by the 68 bounds
+#[allow(dead_code)]
+pub(crate) mod invariants;
+pub(crate) mod spectral;
+pub(crate) mod policyEncoding;
+pub(crate) mod genome;
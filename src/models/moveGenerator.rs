@@ -0,0 +1,379 @@
+use std::collections::VecDeque;
+use crate::models::conjectures::conjectures_wagner_1::{State, Move, MoveKind, Connectivity};
+
+pub trait MoveGenerator {
+    fn name(&self) -> &'static str;
+
+    // moves available from st, the tree searches only ever play these
+    fn legal_moves(&self, st: &State) -> Vec<Move>;
+
+    // true if st belongs to the graph class of the generator
+    fn accepts(&self, st: &State) -> bool;
+
+    // turns st into a member of the graph class, used by the methods that edit adj_mat directly (ILS, CMAES)
+    fn repair(&self, st: &mut State);
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum GraphMode {
+    AnyGraph,
+    Tree,
+    Bipartite,
+    Connected
+}
+
+impl GraphMode {
+    pub fn generator(&self) -> &'static dyn MoveGenerator {
+        match self {
+            GraphMode::AnyGraph => &AnyGraphMoves,
+            GraphMode::Tree => &TreeMoves,
+            GraphMode::Bipartite => &BipartiteMoves,
+            GraphMode::Connected => &ConnectedMoves
+        }
+    }
+}
+
+pub struct AnyGraphMoves;
+pub struct TreeMoves;
+pub struct BipartiteMoves;
+pub struct ConnectedMoves;
+
+fn new_vertex_moves(st: &State, vec: &mut Vec<Move>) {
+    if st.n_sommet < st.size_terminal {
+        for i in 0..st.n_sommet {
+            vec.push(Move{ind: st.n_sommet, from: i, to: -1, kind: MoveKind::AddEdge});
+        }
+    }
+}
+
+// RemoveEdge/RemoveVertex moves, only offered while the state has edits left
+fn removal_moves(st: &State, vec: &mut Vec<Move>, keep_connected: bool) {
+    if st.edits_left() == 0 {
+        return;
+    }
+    let keep_connected = keep_connected || st.connectivity != Connectivity::Ignore;
+
+    let mut candidates = Vec::new();
+    for i in 0..st.n_sommet {
+        for j in (i+1)..st.n_sommet {
+            if st.adj_mat[(i, j)] == 1.0 {
+                candidates.push(Move{ind: st.n_sommet, from: i, to: j as i64, kind: MoveKind::RemoveEdge});
+            }
+        }
+    }
+    if st.n_sommet > 1 {
+        for i in 0..st.n_sommet {
+            candidates.push(Move{ind: st.n_sommet, from: i, to: -1, kind: MoveKind::RemoveVertex});
+        }
+    }
+
+    for m in candidates {
+        if keep_connected {
+            let mut cl = st.clone();
+            cl.play(m);
+            if !cl.is_connected() {
+                continue;
+            }
+        }
+        vec.push(m);
+    }
+}
+
+pub fn count_edges(st: &State) -> usize {
+    let mut n = 0;
+    for i in 0..st.n_sommet {
+        for j in (i+1)..st.n_sommet {
+            if st.adj_mat[(i, j)] == 1.0 {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+// component index, BFS depth and BFS parent of every vertex, components are numbered in order of their smallest vertex
+pub fn bfs_forest(st: &State) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+    let mut comp = vec![usize::MAX; st.n_sommet];
+    let mut depth = vec![0; st.n_sommet];
+    let mut parent = vec![usize::MAX; st.n_sommet];
+    let mut n_comp = 0;
+
+    for root in 0..st.n_sommet {
+        if comp[root] != usize::MAX {
+            continue;
+        }
+        comp[root] = n_comp;
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+        while let Some(v) = queue.pop_front() {
+            for w in 0..st.n_sommet {
+                if st.adj_mat[(v, w)] == 1.0 && comp[w] == usize::MAX {
+                    comp[w] = n_comp;
+                    depth[w] = depth[v] + 1;
+                    parent[w] = v;
+                    queue.push_back(w);
+                }
+            }
+        }
+        n_comp += 1;
+    }
+
+    (comp, depth, parent)
+}
+
+fn component_roots(comp: &[usize]) -> Vec<usize> {
+    let mut roots = Vec::new();
+    for (v, &c) in comp.iter().enumerate() {
+        if c == roots.len() {
+            roots.push(v);
+        }
+    }
+    roots
+}
+
+fn link_components(st: &mut State, comp: &[usize]) {
+    let roots = component_roots(comp);
+    for k in 1..roots.len() {
+        st.adj_mat[(roots[k-1], roots[k])] = 1.0;
+        st.adj_mat[(roots[k], roots[k-1])] = 1.0;
+    }
+}
+
+impl MoveGenerator for AnyGraphMoves {
+    fn name(&self) -> &'static str {
+        "any graph"
+    }
+
+    fn legal_moves(&self, st: &State) -> Vec<Move> {
+        let mut vec: Vec<Move> = Vec::new();
+        new_vertex_moves(st, &mut vec);
+
+        for i in 0..st.n_sommet {
+            for j in (i+1)..st.n_sommet {
+                if st.adj_mat[(i, j)] == 0.0 {
+                    vec.push(Move{ind: st.n_sommet, from: i, to: j as i64, kind: MoveKind::AddEdge});
+                }
+            }
+        }
+        removal_moves(st, &mut vec, false);
+
+        vec
+    }
+
+    fn accepts(&self, _st: &State) -> bool {
+        true
+    }
+
+    fn repair(&self, _st: &mut State) {}
+}
+
+impl MoveGenerator for TreeMoves {
+    fn name(&self) -> &'static str {
+        "tree"
+    }
+
+    // a tree only grows by attaching a new leaf and only shrinks by removing one
+    fn legal_moves(&self, st: &State) -> Vec<Move> {
+        let mut vec: Vec<Move> = Vec::new();
+        new_vertex_moves(st, &mut vec);
+        removal_moves(st, &mut vec, true);
+        vec
+    }
+
+    fn accepts(&self, st: &State) -> bool {
+        st.is_connected() && count_edges(st) + 1 == st.n_sommet
+    }
+
+    // keeps a BFS spanning forest, then chains the roots of its trees
+    fn repair(&self, st: &mut State) {
+        let (comp, _, parent) = bfs_forest(st);
+        st.adj_mat.fill(0.0);
+        for (w, &p) in parent.iter().enumerate() {
+            if p != usize::MAX {
+                st.adj_mat[(p, w)] = 1.0;
+                st.adj_mat[(w, p)] = 1.0;
+            }
+        }
+        link_components(st, &comp);
+        st.n_arete = count_edges(st);
+    }
+}
+
+impl MoveGenerator for BipartiteMoves {
+    fn name(&self) -> &'static str {
+        "bipartite"
+    }
+
+    // two vertices can be joined if they lie in different components or on different sides
+    fn legal_moves(&self, st: &State) -> Vec<Move> {
+        let mut vec: Vec<Move> = Vec::new();
+        new_vertex_moves(st, &mut vec);
+
+        let (comp, depth, _) = bfs_forest(st);
+        for i in 0..st.n_sommet {
+            for j in (i+1)..st.n_sommet {
+                if st.adj_mat[(i, j)] == 0.0 && (comp[i] != comp[j] || depth[i] % 2 != depth[j] % 2) {
+                    vec.push(Move{ind: st.n_sommet, from: i, to: j as i64, kind: MoveKind::AddEdge});
+                }
+            }
+        }
+        removal_moves(st, &mut vec, false);
+
+        vec
+    }
+
+    fn accepts(&self, st: &State) -> bool {
+        let (_, depth, _) = bfs_forest(st);
+        for i in 0..st.n_sommet {
+            for j in (i+1)..st.n_sommet {
+                if st.adj_mat[(i, j)] == 1.0 && depth[i] % 2 == depth[j] % 2 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // drops the edges inside a side of the BFS 2-colouring
+    fn repair(&self, st: &mut State) {
+        let (_, depth, _) = bfs_forest(st);
+        for i in 0..st.n_sommet {
+            for j in (i+1)..st.n_sommet {
+                if st.adj_mat[(i, j)] == 1.0 && depth[i] % 2 == depth[j] % 2 {
+                    st.adj_mat[(i, j)] = 0.0;
+                    st.adj_mat[(j, i)] = 0.0;
+                }
+            }
+        }
+        st.n_arete = count_edges(st);
+    }
+}
+
+impl MoveGenerator for ConnectedMoves {
+    fn name(&self) -> &'static str {
+        "connected"
+    }
+
+    // adding edges or leaves never disconnects a graph, bridges and cut vertices are never removed
+    fn legal_moves(&self, st: &State) -> Vec<Move> {
+        let mut vec: Vec<Move> = Vec::new();
+        new_vertex_moves(st, &mut vec);
+
+        for i in 0..st.n_sommet {
+            for j in (i+1)..st.n_sommet {
+                if st.adj_mat[(i, j)] == 0.0 {
+                    vec.push(Move{ind: st.n_sommet, from: i, to: j as i64, kind: MoveKind::AddEdge});
+                }
+            }
+        }
+        removal_moves(st, &mut vec, true);
+
+        vec
+    }
+
+    fn accepts(&self, st: &State) -> bool {
+        st.is_connected()
+    }
+
+    fn repair(&self, st: &mut State) {
+        let (comp, _, _) = bfs_forest(st);
+        link_components(st, &comp);
+        st.n_arete = count_edges(st);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::calc::{randomF64, setSeed, uniform};
+
+    const MODES: [GraphMode; 4] = [GraphMode::AnyGraph, GraphMode::Tree, GraphMode::Bipartite, GraphMode::Connected];
+
+    fn random_graph(n: usize, mode: GraphMode) -> State {
+        let mut st = State::new().empty_like(n);
+        st.mode = mode;
+        for i in 0..n {
+            for j in (i+1)..n {
+                if randomF64() < 0.4 {
+                    st.adj_mat[(i, j)] = 1.0;
+                    st.adj_mat[(j, i)] = 1.0;
+                }
+            }
+        }
+        st.n_arete = count_edges(&st);
+        st
+    }
+
+    #[test]
+    fn playouts_stay_in_the_graph_class() {
+        setSeed(3);
+        for mode in MODES {
+            for _ in 0..20 {
+                let mut st = State::new();
+                st.mode = mode;
+                st.size_terminal = 8;
+                st.max_edits = 4;
+                for _ in 0..60 {
+                    let moves = st.legal_moves();
+                    if moves.is_empty() {
+                        break
+                    }
+                    st.play(moves[uniform(moves.len())]);
+                    assert!(mode.generator().accepts(&st), "{:?} after {:?}", mode, st.seq);
+                    assert_eq!(st.n_arete, count_edges(&st));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn repair_reaches_the_graph_class() {
+        setSeed(5);
+        for mode in MODES {
+            for n in [1, 2, 5, 9] {
+                let mut st = random_graph(n, mode);
+                mode.generator().repair(&mut st);
+                assert!(mode.generator().accepts(&st), "{:?} on {} vertices", mode, n);
+                assert_eq!(st.n_arete, count_edges(&st));
+            }
+        }
+        // a disconnected graph is linked up, a tree is left alone
+        let mut two = State::new().empty_like(4);
+        two.add_arete(0, 1);
+        two.add_arete(2, 3);
+        assert!(!ConnectedMoves.accepts(&two));
+        ConnectedMoves.repair(&mut two);
+        assert!(TreeMoves.accepts(&two));
+        let before = two.adj_mat.clone();
+        TreeMoves.repair(&mut two);
+        assert_eq!(two.adj_mat, before);
+    }
+
+    #[test]
+    fn kept_connected_removals_never_disconnect() {
+        setSeed(9);
+        for _ in 0..20 {
+            let mut st = random_graph(8, GraphMode::Connected);
+            ConnectedMoves.repair(&mut st);
+            st.max_edits = 1;
+            let mut moves = Vec::new();
+            removal_moves(&st, &mut moves, true);
+            assert!(!moves.is_empty());
+            for m in moves {
+                let mut cl = st.clone();
+                cl.play(m);
+                assert!(cl.is_connected(), "{:?}", m);
+            }
+        }
+        // a path only loses its ends
+        let mut path = State::new().empty_like(4);
+        for i in 0..3 {
+            path.add_arete(i, i as i32 + 1);
+        }
+        path.max_edits = 1;
+        let mut moves = Vec::new();
+        removal_moves(&path, &mut moves, true);
+        assert_eq!(moves.len(), 2);
+        assert!(moves.iter().all(|m| m.kind == MoveKind::RemoveVertex && (m.from == 0 || m.from == 3)));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::models::conjectures::conjectures_wagner_1::{State, Move, MoveKind};
+
+// what an NRPA weight is attached to, every encoding but MoveIdentity is shared by moves of different states
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum PolicyEncoding {
+    MoveIdentity,
+    DegreeClasses,
+    MoveType,
+    Distance
+}
+
+// AddEdge/ToggleEdge moves either bring a new vertex, join two components or close a cycle, RemoveVertex moves have their own type
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum MoveType {
+    NewVertex,
+    RemoveVertex,
+    JoinComponents,
+    CloseCycle,
+    Other
+}
+
+// new vertices and unreachable endpoints are stored as usize::MAX
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum PolicyKey {
+    Move(Move),
+    Degrees(MoveKind, usize, usize),
+    Type(MoveKind, MoveType),
+    Distance(MoveKind, usize)
+}
+
+impl PolicyEncoding {
+    // keys of all the moves of st, the degrees are computed once per state and the distances come from the invariant cache
+    pub fn keys(&self, st: &State, moves: &[Move]) -> Vec<PolicyKey> {
+        match self {
+            PolicyEncoding::MoveIdentity => moves.iter().map(|&m| PolicyKey::Move(m)).collect(),
+            PolicyEncoding::DegreeClasses => {
+                let deg = st.degrees();
+                moves.iter().map(|m| {
+                    let d_from = deg[m.from] as usize;
+                    let d_to = if m.to < 0 { usize::MAX } else { deg[m.to as usize] as usize };
+                    PolicyKey::Degrees(m.kind, d_from.min(d_to), d_from.max(d_to))
+                }).collect()
+            },
+            PolicyEncoding::MoveType => {
+                let dist = st.distance_matrix();
+                moves.iter().map(|m| {
+                    let t = if m.kind == MoveKind::RemoveVertex {
+                        MoveType::RemoveVertex
+                    } else if m.to < 0 {
+                        MoveType::NewVertex
+                    } else if m.kind != MoveKind::AddEdge && m.kind != MoveKind::ToggleEdge {
+                        MoveType::Other
+                    } else if dist[(m.from, m.to as usize)].is_infinite() {
+                        MoveType::JoinComponents
+                    } else {
+                        MoveType::CloseCycle
+                    };
+                    PolicyKey::Type(m.kind, t)
+                }).collect()
+            },
+            PolicyEncoding::Distance => {
+                let dist = st.distance_matrix();
+                moves.iter().map(|m| {
+                    let d = if m.to < 0 || dist[(m.from, m.to as usize)].is_infinite() {
+                        usize::MAX
+                    } else {
+                        dist[(m.from, m.to as usize)] as usize
+                    };
+                    PolicyKey::Distance(m.kind, d)
+                }).collect()
+            }
+        }
+    }
+}
+
+fn kind_from_name(name: &str) -> Option<MoveKind> {
+    match name {
+        "AddEdge" => Some(MoveKind::AddEdge),
+        "RemoveEdge" => Some(MoveKind::RemoveEdge),
+        "ToggleEdge" => Some(MoveKind::ToggleEdge),
+        "RemoveVertex" => Some(MoveKind::RemoveVertex),
+        _ => None
+    }
+}
+
+fn type_from_name(name: &str) -> Option<MoveType> {
+    match name {
+        "NewVertex" => Some(MoveType::NewVertex),
+        "RemoveVertex" => Some(MoveType::RemoveVertex),
+        "JoinComponents" => Some(MoveType::JoinComponents),
+        "CloseCycle" => Some(MoveType::CloseCycle),
+        "Other" => Some(MoveType::Other),
+        _ => None
+    }
+}
+
+fn count_from_name(name: &str) -> Option<usize> {
+    if name == "none" {
+        return Some(usize::MAX);
+    }
+    name.parse().ok()
+}
+
+// "ind, from, to, kind", the format of a move in the policy and transposition table files
+pub fn move_to_words(m: &Move) -> String {
+    format!("{}, {}, {}, {:?}", m.ind, m.from, m.to, m.kind)
+}
+
+pub fn move_from_words(words: &[&str]) -> Option<Move> {
+    match words {
+        [ind, from, to, kind] => Some(Move{ind: ind.parse().ok()?, from: from.parse().ok()?, to: to.parse().ok()?, kind: kind_from_name(kind)?}),
+        _ => None
+    }
+}
+
+fn count_to_name(n: usize) -> String {
+    if n == usize::MAX {
+        "none".to_string()
+    } else {
+        n.to_string()
+    }
+}
+
+impl PolicyKey {
+    pub fn to_words(self) -> String {
+        match self {
+            PolicyKey::Move(m) => format!("move, {}", move_to_words(&m)),
+            PolicyKey::Degrees(kind, d1, d2) => format!("degrees, {:?}, {}, {}", kind, count_to_name(d1), count_to_name(d2)),
+            PolicyKey::Type(kind, t) => format!("type, {:?}, {:?}", kind, t),
+            PolicyKey::Distance(kind, d) => format!("distance, {:?}, {}", kind, count_to_name(d))
+        }
+    }
+
+    // inverse of the policy file format, the encoding first then the fields separated by ", "
+    pub fn parse(words: &[&str]) -> Option<PolicyKey> {
+        match words {
+            ["move", m @ ..] => Some(PolicyKey::Move(move_from_words(m)?)),
+            ["degrees", kind, d1, d2] => Some(PolicyKey::Degrees(kind_from_name(kind)?, count_from_name(d1)?, count_from_name(d2)?)),
+            ["type", kind, t] => Some(PolicyKey::Type(kind_from_name(kind)?, type_from_name(t)?)),
+            ["distance", kind, d] => Some(PolicyKey::Distance(kind_from_name(kind)?, count_from_name(d)?)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(n: usize) -> State {
+        let mut st = State::new().empty_like(n);
+        for i in 1..n {
+            st.add_arete(i - 1, i as i32);
+        }
+        st
+    }
+
+    #[test]
+    fn removals_have_their_own_type() {
+        let st = path(4);
+        let moves = [Move{ind: 0, from: 0, to: -1, kind: MoveKind::AddEdge},
+                     Move{ind: 1, from: 0, to: -1, kind: MoveKind::RemoveVertex},
+                     Move{ind: 2, from: 0, to: 3, kind: MoveKind::AddEdge},
+                     Move{ind: 3, from: 0, to: 1, kind: MoveKind::RemoveEdge}];
+        let keys = PolicyEncoding::MoveType.keys(&st, &moves);
+        assert_eq!(keys[0], PolicyKey::Type(MoveKind::AddEdge, MoveType::NewVertex));
+        assert_eq!(keys[1], PolicyKey::Type(MoveKind::RemoveVertex, MoveType::RemoveVertex));
+        assert_eq!(keys[2], PolicyKey::Type(MoveKind::AddEdge, MoveType::CloseCycle));
+        assert_eq!(keys[3], PolicyKey::Type(MoveKind::RemoveEdge, MoveType::Other));
+    }
+
+    #[test]
+    fn distances_and_degrees() {
+        let mut st = path(4);
+        st.add_arete(0, -1);
+        let moves = [Move{ind: 0, from: 0, to: 3, kind: MoveKind::AddEdge},
+                     Move{ind: 1, from: 1, to: -1, kind: MoveKind::AddEdge}];
+        let keys = PolicyEncoding::Distance.keys(&st, &moves);
+        assert_eq!(keys, vec![PolicyKey::Distance(MoveKind::AddEdge, 3), PolicyKey::Distance(MoveKind::AddEdge, usize::MAX)]);
+        let keys = PolicyEncoding::DegreeClasses.keys(&st, &moves);
+        assert_eq!(keys, vec![PolicyKey::Degrees(MoveKind::AddEdge, 1, 2), PolicyKey::Degrees(MoveKind::AddEdge, 2, usize::MAX)]);
+    }
+
+    #[test]
+    fn keys_round_trip_through_words() {
+        let keys = [PolicyKey::Move(Move{ind: 3, from: 1, to: -1, kind: MoveKind::ToggleEdge}),
+                    PolicyKey::Degrees(MoveKind::RemoveEdge, 2, usize::MAX),
+                    PolicyKey::Type(MoveKind::RemoveVertex, MoveType::RemoveVertex),
+                    PolicyKey::Distance(MoveKind::AddEdge, 4)];
+        for key in keys {
+            let words = key.to_words();
+            assert_eq!(PolicyKey::parse(&words.split(", ").collect::<Vec<&str>>()), Some(key));
+        }
+        assert_eq!(PolicyKey::parse(&["type", "AddEdge", "Sideways"]), None);
+    }
+}
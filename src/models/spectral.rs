@@ -0,0 +1,156 @@
+use nalgebra::DMatrix;
+use nalgebra::linalg::SymmetricEigen;
+use crate::models::conjectures::conjectures_wagner_1::State;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum GraphMatrix {
+    Adjacency,
+    // L = D - A
+    Laplacian,
+    // Q = D + A
+    SignlessLaplacian,
+    // I - D^-1/2 A D^-1/2, with a zero diagonal entry for isolated vertices
+    NormalizedLaplacian,
+    Distance,
+    // Tr - D, Tr being the diagonal matrix of the transmissions
+    DistanceLaplacian
+}
+
+impl GraphMatrix {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            GraphMatrix::Adjacency => "A",
+            GraphMatrix::Laplacian => "L",
+            GraphMatrix::SignlessLaplacian => "Q",
+            GraphMatrix::NormalizedLaplacian => "NL",
+            GraphMatrix::Distance => "D",
+            GraphMatrix::DistanceLaplacian => "DL"
+        }
+    }
+}
+
+impl State {
+    pub fn graph_matrix(& self, m : GraphMatrix) -> DMatrix<f64> {
+        let n = self.n_sommet;
+        match m {
+            GraphMatrix::Adjacency => self.adj_mat.clone(),
+            GraphMatrix::Laplacian => self.degree_matrix() - self.adj_mat.clone(),
+            GraphMatrix::SignlessLaplacian => self.degree_matrix() + self.adj_mat.clone(),
+            GraphMatrix::NormalizedLaplacian => {
+                let inv_sqrt: Vec<f64> = self.degrees().iter().map(|&d| if d > 0.0 { 1.0 / d.sqrt() } else { 0.0 }).collect();
+                DMatrix::from_fn(n, n, |i, j| {
+                    let diag = if i == j && inv_sqrt[i] > 0.0 { 1.0 } else { 0.0 };
+                    diag - inv_sqrt[i] * self.adj_mat[(i, j)] * inv_sqrt[j]
+                })
+            },
+            GraphMatrix::Distance => self.distance_matrix(),
+            GraphMatrix::DistanceLaplacian => {
+                let dist = self.distance_matrix();
+                let transmissions: Vec<f64> = (0..n).map(|i| dist.row(i).sum()).collect();
+                DMatrix::from_fn(n, n, |i, j| if i == j { transmissions[i] } else { -dist[(i, j)] })
+            }
+        }
+    }
+
+    // eigenvalues in increasing order, cached per graph; the distance spectra of a disconnected graph are NaN
+    pub fn spectrum(& self, m : GraphMatrix) -> Vec<f64> {
+        if let Some(sp) = self.cache().spectra.get(&m) {
+            return sp.clone();
+        }
+
+        let sp = if (m == GraphMatrix::Distance || m == GraphMatrix::DistanceLaplacian) && !self.is_connected() {
+            vec![f64::NAN; self.n_sommet]
+        } else {
+            let mut eigenvalues: Vec<f64> = SymmetricEigen::new(self.graph_matrix(m)).eigenvalues.iter().copied().collect();
+            eigenvalues.sort_by(|a, b| a.total_cmp(b));
+            eigenvalues
+        };
+        self.cache().spectra.insert(m, sp.clone());
+        sp
+    }
+
+    pub fn largest_eigenvalue(& self, m : GraphMatrix) -> f64 {
+        *self.spectrum(m).last().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(n: usize, edges: &[(usize, usize)]) -> State {
+        let mut st = State::new().empty_like(n);
+        for &(i, j) in edges {
+            st.add_arete(i, j as i32);
+        }
+        st
+    }
+
+    fn complete(n: usize) -> State {
+        let edges: Vec<(usize, usize)> = (0..n).flat_map(|i| ((i+1)..n).map(move |j| (i, j))).collect();
+        graph(n, &edges)
+    }
+
+    fn cycle(n: usize) -> State {
+        let edges: Vec<(usize, usize)> = (0..n).map(|i| (i, (i + 1) % n)).collect();
+        graph(n, &edges)
+    }
+
+    fn assert_spectrum(sp: &[f64], expected: &[f64]) {
+        assert_eq!(sp.len(), expected.len());
+        for (a, b) in sp.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-9, "{:?} != {:?}", sp, expected);
+        }
+    }
+
+    #[test]
+    fn complete_graph_spectra() {
+        let k5 = complete(5);
+        // A = J - I, L = nI - J, the distance matrix of K_n is its adjacency matrix
+        assert_spectrum(&k5.spectrum(GraphMatrix::Adjacency), &[-1.0, -1.0, -1.0, -1.0, 4.0]);
+        assert_spectrum(&k5.spectrum(GraphMatrix::Laplacian), &[0.0, 5.0, 5.0, 5.0, 5.0]);
+        assert_spectrum(&k5.spectrum(GraphMatrix::SignlessLaplacian), &[3.0, 3.0, 3.0, 3.0, 8.0]);
+        assert_spectrum(&k5.spectrum(GraphMatrix::Distance), &[-1.0, -1.0, -1.0, -1.0, 4.0]);
+        assert_spectrum(&k5.spectrum(GraphMatrix::DistanceLaplacian), &[0.0, 5.0, 5.0, 5.0, 5.0]);
+        assert_spectrum(&k5.spectrum(GraphMatrix::NormalizedLaplacian), &[0.0, 1.25, 1.25, 1.25, 1.25]);
+        assert!((k5.largest_eigenvalue(GraphMatrix::Adjacency) - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cycle_spectra() {
+        for n in [4, 5, 8] {
+            let c = cycle(n);
+            // 2-regular: Q = 2I + A has largest eigenvalue 4, L = 2I - A has a single 0
+            assert!((c.largest_eigenvalue(GraphMatrix::SignlessLaplacian) - 4.0).abs() < 1e-9);
+            assert!((c.largest_eigenvalue(GraphMatrix::Adjacency) - 2.0).abs() < 1e-9);
+            let l = c.spectrum(GraphMatrix::Laplacian);
+            assert!(l[0].abs() < 1e-9 && l[1] > 1e-9);
+        }
+        // an even cycle is bipartite: Q has a 0 and the normalized Laplacian a 2
+        let c8 = cycle(8);
+        assert!(c8.spectrum(GraphMatrix::SignlessLaplacian)[0].abs() < 1e-9);
+        assert!((c8.largest_eigenvalue(GraphMatrix::NormalizedLaplacian) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn disconnected_spectra() {
+        // a triangle, an edge and an isolated vertex
+        let st = graph(6, &[(0, 1), (1, 2), (2, 0), (3, 4)]);
+        let l = st.spectrum(GraphMatrix::Laplacian);
+        assert_eq!(l.iter().filter(|x| x.abs() < 1e-9).count(), 3);
+        assert_spectrum(&l, &[0.0, 0.0, 0.0, 2.0, 3.0, 3.0]);
+        // the isolated vertex adds a 0 to the normalized Laplacian, the edge a 2
+        assert_spectrum(&st.spectrum(GraphMatrix::NormalizedLaplacian), &[0.0, 0.0, 0.0, 1.5, 1.5, 2.0]);
+        assert!(st.spectrum(GraphMatrix::Distance).iter().all(|x| x.is_nan()));
+        assert!(st.spectrum(GraphMatrix::DistanceLaplacian).iter().all(|x| x.is_nan()));
+    }
+
+    #[test]
+    fn spectra_follow_the_graph() {
+        let mut st = cycle(4);
+        assert!((st.largest_eigenvalue(GraphMatrix::Adjacency) - 2.0).abs() < 1e-9);
+        st.add_arete(0, 2);
+        st.add_arete(1, 3);
+        assert!((st.largest_eigenvalue(GraphMatrix::Adjacency) - 3.0).abs() < 1e-9);
+    }
+}
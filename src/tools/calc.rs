@@ -1,19 +1,245 @@
-pub fn softmaxChoice(l : Vec<f64>) -> usize {
-    let r = rand::random::<f64>();
+use std::cell::RefCell;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::models::conjectures::conjectures_wagner_1::{State, Move};
 
-    let mut sum = 0.0;
-    for i in 0..l.len() {
-        sum += l[i].exp();
+thread_local! {
+    // every random draw of the searches goes through this generator so that a checkpoint can restart its stream
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+// how a move is drawn from its weights, top_k = 0 keeps every move
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Sampler {
+    pub temperature: f64,
+    pub top_k: usize,
+    pub gumbel: bool
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self {
+            temperature: 1.0,
+            top_k: 0,
+            gumbel: false
+        }
+    }
+}
+
+pub fn withRng<T>(f : impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+// uniform in [0, 1)
+pub fn randomF64() -> f64 {
+    withRng(|rng| rng.gen::<f64>())
+}
+
+// uniform in 0..n
+pub fn uniform(n: usize) -> usize {
+    ((n as f64)*randomF64()) as usize
+}
+
+pub fn setSeed(seed : u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// draws a new seed and restarts the stream from it, the seed is what a checkpoint stores
+pub fn reseed() -> u64 {
+    let seed = withRng(|rng| rng.gen::<u64>());
+    setSeed(seed);
+    seed
+}
+
+// log(sum(exp(l))) without overflow, -inf for an empty list or a list of -inf
+pub fn logSumExp(l : &[f64]) -> f64 {
+    let max = l.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if !max.is_finite() {
+        return max;
+    }
+    max + l.iter().map(|x| (x - max).exp()).sum::<f64>().ln()
+}
+
+// probabilities of exp(l/temperature), uniform if every weight is -inf;
+// a temperature of 0 (or below) is the limit of the softmax, uniform over the largest weights
+pub fn softmax(l : &[f64], temperature : f64) -> Vec<f64> {
+    if temperature <= 0.0 {
+        let max = l.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if max == f64::NEG_INFINITY || max.is_nan() {
+            return vec![1.0 / l.len() as f64; l.len()];
+        }
+        let n_max = l.iter().filter(|&&x| x == max).count() as f64;
+        return l.iter().map(|&x| if x == max { 1.0 / n_max } else { 0.0 }).collect();
+    }
+    let scaled: Vec<f64> = l.iter().map(|x| x / temperature).collect();
+    let max = scaled.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY || max.is_nan() {
+        return vec![1.0 / l.len() as f64; l.len()];
+    }
+    if max == f64::INFINITY {
+        // only the infinite weights can be drawn
+        let n_inf = scaled.iter().filter(|&&x| x == f64::INFINITY).count() as f64;
+        return scaled.iter().map(|&x| if x == f64::INFINITY { 1.0 / n_inf } else { 0.0 }).collect();
+    }
+    let lse = logSumExp(&scaled);
+    scaled.iter().map(|x| (x - lse).exp()).collect()
+}
+
+// the k largest weights are kept, the others set to -inf
+pub fn topK(l : &[f64], k : usize) -> Vec<f64> {
+    if k == 0 || k >= l.len() {
+        return l.to_vec();
+    }
+    let mut sorted = l.to_vec();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let threshold = sorted[k - 1];
+
+    let mut kept = 0;
+    let mut res = Vec::new();
+    for &x in l {
+        if x >= threshold && kept < k {
+            res.push(x);
+            kept += 1;
+        } else {
+            res.push(f64::NEG_INFINITY);
+        }
+    }
+    res
+}
+
+// argmax of l/temperature + Gumbel noise, draws from the same distribution as the softmax
+pub fn gumbelMaxChoice(l : &[f64], temperature : f64) -> usize {
+    if temperature <= 0.0 {
+        return draw(&softmax(l, temperature));
     }
+    let mut best = 0;
+    let mut best_value = f64::NEG_INFINITY;
+    for (i, x) in l.iter().enumerate() {
+        let u = randomF64().max(f64::MIN_POSITIVE);
+        let v = x / temperature - (-u.ln()).ln();
+        if v > best_value {
+            best_value = v;
+            best = i;
+        }
+    }
+    if best_value == f64::NEG_INFINITY {
+        return (l.len() as f64 * randomF64()) as usize;
+    }
+    best
+}
+
+// probabilities with which softmaxChoice draws each move (the Gumbel draw follows the same distribution)
+pub fn samplerProbs(l : &[f64], sampler : &Sampler) -> Vec<f64> {
+    softmax(&topK(l, sampler.top_k), sampler.temperature)
+}
+
+pub fn softmaxChoice(l : Vec<f64>, sampler : &Sampler) -> usize {
+    if sampler.gumbel {
+        return gumbelMaxChoice(&topK(&l, sampler.top_k), sampler.temperature);
+    }
+    draw(&samplerProbs(&l, sampler))
+}
 
-    let mut sum2 = 0.0;
-    for i in 0..l.len() {
-        sum2+= l[i].exp()/sum;
-        if sum2 >= r{
+// index drawn with the probabilities probs
+fn draw(probs : &[f64]) -> usize {
+    let r = randomF64();
+    let mut sum = 0.0;
+    for (i, p) in probs.iter().enumerate() {
+        sum += p;
+        if sum >= r {
             return i;
         }
     }
 
-    println!("The heuristic may be too big, or the algo cannot differentiate paths.");
-    return l.len() - 1;
+    // rounding can leave the sum just below r, the last move with a chance of being drawn is taken
+    probs.iter().rposition(|&p| p > 0.0).unwrap_or(probs.len() - 1)
+}
+
+// softmax weights of the moves, a move with an undefined heuristic gets -inf (never drawn), None if no move has a defined one
+pub fn heuristicWeights(st: &mut State, moves: &[Move], heuristic_w: f64) -> Option<Vec<f64>> {
+    let mut weights = Vec::new();
+    let mut defined = false;
+    for &m in moves {
+        let h = st.heuristic(m);
+        if h.is_nan() {
+            weights.push(f64::NEG_INFINITY);
+        } else {
+            defined = true;
+            weights.push(heuristic_w*h);
+        }
+    }
+
+    if defined {
+        Some(weights)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn log_sum_exp() {
+        assert!(close(logSumExp(&[0.0, 0.0]), 2f64.ln()));
+        assert!(close(logSumExp(&[1000.0, 1000.0]), 1000.0 + 2f64.ln()));
+        assert_eq!(logSumExp(&[]), f64::NEG_INFINITY);
+        assert_eq!(logSumExp(&[f64::NEG_INFINITY, f64::NEG_INFINITY]), f64::NEG_INFINITY);
+        assert!(close(logSumExp(&[f64::NEG_INFINITY, 3.0]), 3.0));
+    }
+
+    #[test]
+    fn softmax_probabilities() {
+        let p = softmax(&[1.0, 2.0, 3.0], 1.0);
+        let z = 1f64.exp() + 2f64.exp() + 3f64.exp();
+        assert!(close(p[2], 3f64.exp() / z));
+        assert!(close(p.iter().sum::<f64>(), 1.0));
+
+        // large weights do not overflow
+        let p = softmax(&[1e6, 1e6 - 1.0], 1.0);
+        assert!(close(p[0], 1.0 / (1.0 + (-1f64).exp())));
+
+        assert_eq!(softmax(&[f64::NEG_INFINITY, f64::NEG_INFINITY], 1.0), vec![0.5, 0.5]);
+        assert_eq!(softmax(&[f64::INFINITY, 1.0, f64::INFINITY], 1.0), vec![0.5, 0.0, 0.5]);
+        assert_eq!(softmax(&[f64::NEG_INFINITY, 1.0], 1.0), vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn zero_temperature_is_argmax() {
+        assert_eq!(softmax(&[1.0, 3.0, 2.0, 3.0], 0.0), vec![0.0, 0.5, 0.0, 0.5]);
+        assert_eq!(softmax(&[f64::NEG_INFINITY; 2], 0.0), vec![0.5, 0.5]);
+        for _ in 0..20 {
+            let i = gumbelMaxChoice(&[1.0, 3.0, 2.0], 0.0);
+            assert_eq!(i, 1);
+            let sampler = Sampler{temperature: 0.0, top_k: 0, gumbel: false};
+            assert_eq!(softmaxChoice(vec![1.0, 3.0, 2.0], &sampler), 1);
+        }
+    }
+
+    #[test]
+    fn top_k() {
+        let n = f64::NEG_INFINITY;
+        assert_eq!(topK(&[1.0, 5.0, 3.0, 4.0], 2), vec![n, 5.0, n, 4.0]);
+        assert_eq!(topK(&[1.0, 5.0], 0), vec![1.0, 5.0]);
+        assert_eq!(topK(&[1.0, 5.0], 3), vec![1.0, 5.0]);
+        // ties keep the first ones
+        assert_eq!(topK(&[2.0, 2.0, 2.0], 2), vec![2.0, 2.0, n]);
+
+        let sampler = Sampler{temperature: 1.0, top_k: 1, gumbel: false};
+        assert_eq!(samplerProbs(&[1.0, 5.0, 3.0], &sampler), vec![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn seeded_stream_repeats() {
+        setSeed(7);
+        let a: Vec<f64> = (0..5).map(|_| randomF64()).collect();
+        setSeed(7);
+        let b: Vec<f64> = (0..5).map(|_| randomF64()).collect();
+        assert_eq!(a, b);
+    }
 }
@@ -0,0 +1,220 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufRead};
+use std::time::{Duration, Instant};
+use crate::models::conjectures::conjectures_wagner_1::{Move, State};
+use crate::models::policyEncoding::{move_to_words, move_from_words};
+use crate::tools::calc::{reseed, setSeed};
+use crate::tools::resultSaver::createFile;
+
+// what a search needs to continue after being killed: the budget already spent, the seed its random stream restarts from,
+// the best state, the current position with a step counter, a pool of weighted states (open list of BFS, parents of the EDA, ...)
+// and named vectors of numbers (mean and covariance of CMA-ES, ...)
+pub struct Checkpoint {
+    pub elapsed: f64,
+    pub seed: u64,
+    pub step: usize,
+    pub best_score: f64,
+    pub best: State,
+    pub position: State,
+    pub pool: Vec<(f64, State)>,
+    pub vectors: Vec<(String, Vec<f64>)>
+}
+
+impl Checkpoint {
+    // reseeds the random generator, the run goes on from the stored seed whether or not it is killed
+    pub fn new(start_time: &Instant, best_score: f64, best: &State, position: &State) -> Self {
+        Self {
+            elapsed: start_time.elapsed().as_secs_f64(),
+            seed: reseed(),
+            step: 0,
+            best_score,
+            best: best.clone(),
+            position: position.clone(),
+            pool: Vec::new(),
+            vectors: Vec::new()
+        }
+    }
+
+    pub fn vector(&self, name: &str) -> Option<&[f64]> {
+        self.vectors.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_slice())
+    }
+
+    // start time of the resumed run such that the elapsed budget goes on from the checkpoint, and the random stream restarted
+    pub fn restore(&self) -> Instant {
+        setSeed(self.seed);
+        Instant::now().checked_sub(Duration::from_secs_f64(self.elapsed)).unwrap_or_else(Instant::now)
+    }
+}
+
+// where the search registered as registerName keeps its checkpoint of the conjecture of st
+pub fn checkpointName(registerName : &str, st : &State) -> String {
+    format!("{}/checkpoint{}", registerName, st.conjecture_name())
+}
+
+// true (and last reset) when st asks for checkpoints and the last one is older than st.checkpoint_every seconds
+pub fn checkpointDue(st : &State, last : &mut Instant) -> bool {
+    if st.checkpoint_every <= 0.0 || last.elapsed().as_secs_f64() < st.checkpoint_every {
+        return false;
+    }
+    *last = Instant::now();
+    true
+}
+
+// "n_sommet, n_arete, best_score, m1; m2; ..., upper triangle of the adjacency matrix"
+fn state_to_words(st : &State) -> String {
+    let moves: Vec<String> = st.seq.iter().map(|m| move_to_words(m).replace(", ", " ")).collect();
+    let mut mat = Vec::new();
+    for i in 0..st.n_sommet {
+        for j in (i+1)..st.n_sommet {
+            mat.push(st.adj_mat[(i, j)].to_string());
+        }
+    }
+    format!("{}, {}, {}, {}, {}", st.n_sommet, st.n_arete, st.best_score, moves.join("; "), mat.join(" "))
+}
+
+// the settings (conjecture, mode, ...) are the ones of init, only the graph and its sequence are read
+fn state_from_words(words : &[&str], init : &State) -> Option<State> {
+    if words.len() != 5 {
+        return None;
+    }
+    let n: usize = words[0].parse().ok()?;
+    let mut st = init.empty_like(n);
+    st.n_arete = words[1].parse().ok()?;
+    st.best_score = words[2].parse().ok()?;
+    st.seq = words[3].split("; ").filter(|m| !m.is_empty())
+        .map(|m| move_from_words(&m.split(' ').collect::<Vec<&str>>())).collect::<Option<Vec<Move>>>()?;
+
+    let mut values = words[4].split(' ').filter(|v| !v.is_empty());
+    for i in 0..n {
+        for j in (i+1)..n {
+            let v: f64 = values.next()?.parse().ok()?;
+            st.adj_mat[(i, j)] = v;
+            st.adj_mat[(j, i)] = v;
+        }
+    }
+    Some(st)
+}
+
+// a checkpoint that cannot be written is reported, the search goes on without it
+pub fn saveCheckpoint(name : &str, cp : &Checkpoint) {
+    if let Err(e) = writeCheckpoint(name, cp) {
+        println!("error, cannot save the checkpoint {}: {}", name, e);
+    }
+}
+
+// one line per field, "best, score, state", "position, state", "pool, weight, state" for the pool and "vector, name, v1 v2 ..."
+fn writeCheckpoint(name : &str, cp : &Checkpoint) -> std::io::Result<()> {
+    // written aside then renamed, a run killed while saving keeps the previous checkpoint
+    let tmp = format!("savedMatrix/{}.tmp", name);
+    let mut file = createFile(&tmp)?;
+    writeln!(file, "elapsed, {}", cp.elapsed)?;
+    writeln!(file, "seed, {}", cp.seed)?;
+    writeln!(file, "step, {}", cp.step)?;
+    writeln!(file, "best, {}, {}", cp.best_score, state_to_words(&cp.best))?;
+    writeln!(file, "position, {}", state_to_words(&cp.position))?;
+    for (w, st) in &cp.pool {
+        writeln!(file, "pool, {}, {}", w, state_to_words(st))?;
+    }
+    for (name, v) in &cp.vectors {
+        let values: Vec<String> = v.iter().map(|x| x.to_string()).collect();
+        writeln!(file, "vector, {}, {}", name, values.join(" "))?;
+    }
+    fs::rename(tmp, format!("savedMatrix/{}.txt", name))
+}
+
+// None if there is no checkpoint or it cannot be read, the search then starts from scratch
+pub fn readCheckpoint(name : &str, init : &State) -> Option<Checkpoint> {
+    let file = File::open(format!("savedMatrix/{}.txt", name)).ok()?;
+
+    let mut elapsed = None;
+    let mut seed = None;
+    let mut step = None;
+    let mut best = None;
+    let mut position = None;
+    let mut pool = Vec::new();
+    let mut vectors = Vec::new();
+    for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+        let words: Vec<&str> = line.split(", ").collect();
+        match words[0] {
+            "elapsed" => elapsed = words.get(1)?.parse().ok(),
+            "seed" => seed = words.get(1)?.parse().ok(),
+            "step" => step = words.get(1)?.parse().ok(),
+            "best" => best = Some((words.get(1)?.parse().ok()?, state_from_words(&words[2..], init)?)),
+            "position" => position = state_from_words(&words[1..], init),
+            "pool" => pool.push((words.get(1)?.parse().ok()?, state_from_words(&words[2..], init)?)),
+            "vector" => {
+                let values = words.get(2).unwrap_or(&"").split(' ').filter(|v| !v.is_empty())
+                    .map(|v| v.parse().ok()).collect::<Option<Vec<f64>>>()?;
+                vectors.push((words.get(1)?.to_string(), values));
+            },
+            _ => {}
+        }
+    }
+
+    let (best_score, best) = best?;
+    Some(Checkpoint{elapsed: elapsed?, seed: seed?, step: step?, best_score, best, position: position?, pool, vectors})
+}
+
+// the checkpoint of st if it asks to resume and there is one
+pub fn resumeCheckpoint(name : &str, st : &State) -> Option<Checkpoint> {
+    if !st.resume {
+        return None;
+    }
+    let cp = readCheckpoint(name, st);
+    match &cp {
+        Some(cp) => println!("resuming {} after {}s", name, cp.elapsed),
+        None => println!("no checkpoint {}, starting from scratch", name)
+    }
+    cp
+}
+
+// a finished run leaves no checkpoint, resuming it starts a new one
+pub fn removeCheckpoint(name : &str) {
+    let _ = fs::remove_file(format!("savedMatrix/{}.txt", name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let mut init = State::new();
+        init.size_terminal = 6;
+        let mut position = init.clone();
+        for _ in 0..4 {
+            let m = position.legal_moves()[0];
+            position.play(m);
+        }
+        let mut best = position.clone();
+        best.best_score = -1.25;
+
+        let mut cp = Checkpoint::new(&Instant::now(), -1.25, &best, &position);
+        cp.step = 7;
+        cp.pool = vec![(0.5, position.clone()), (f64::NEG_INFINITY, init.clone())];
+        cp.vectors = vec![("mean".to_string(), vec![0.1, -2.0, 1e-30]), ("empty".to_string(), vec![])];
+        // the folder of the checkpoint is created on the way
+        let name = "checkpointRoundTrip/checkpoint1";
+        writeCheckpoint(name, &cp).unwrap();
+        let back = readCheckpoint(name, &init);
+        removeCheckpoint(name);
+        let exists = std::path::Path::new("savedMatrix/checkpointRoundTrip").exists();
+        let _ = fs::remove_dir_all("savedMatrix/checkpointRoundTrip");
+        assert!(exists);
+
+        let back = back.unwrap();
+        assert_eq!((back.elapsed, back.seed, back.step, back.best_score), (cp.elapsed, cp.seed, 7, -1.25));
+        assert_eq!(back.best.adj_mat, best.adj_mat);
+        assert_eq!(back.best.best_score, -1.25);
+        assert_eq!(back.position.seq.len(), 4);
+        assert_eq!(back.position.seq, position.seq);
+        assert_eq!(back.position.n_arete, position.n_arete);
+        assert_eq!(back.pool.len(), 2);
+        assert_eq!(back.pool[1].0, f64::NEG_INFINITY);
+        assert_eq!(back.pool[0].1.adj_mat, position.adj_mat);
+        assert_eq!(back.vector("mean"), Some(&[0.1, -2.0, 1e-30][..]));
+        assert_eq!(back.vector("empty"), Some(&[][..]));
+        assert!(readCheckpoint(name, &init).is_none());
+    }
+}
@@ -2,3 +2,6 @@ pub(crate) mod graphToDot;
 pub(crate) mod calc;
 pub(crate) mod saveMatrix;
 pub(crate) mod resultSaver;
+pub(crate) mod savePolicy;
+pub(crate) mod saveTransTable;
+pub(crate) mod checkpoint;
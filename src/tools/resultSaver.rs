@@ -1,7 +1,18 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::Path;
+use std::time::Instant;
+use crate::models::conjectures::conjectures_wagner_1::State;
+use crate::tools::{graphToDot, saveMatrix};
+
+// creates the file at path, and the folders on the way that do not exist yet (savedMatrix/<registerName>, ...)
+pub fn createFile(path : &str) -> std::io::Result<File> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    File::create(path)
+}
 
 pub fn writeLine(line : String, name : String ) -> std::io::Result<()>{
     if(name.len() != 0){
@@ -20,4 +31,49 @@ pub fn writeLine(line : String, name : String ) -> std::io::Result<()>{
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+// prints a new best state of method and logs it in results/<registerName>_evolution.txt
+pub fn logBest(st: &State, score: f64, start_time: &Instant, method: &str, verbose: bool, registerName: &str) {
+    let elapsed = start_time.elapsed().as_secs_f64();
+    println!("{} best score yet : {} after {}", method, score, elapsed);
+    if verbose {
+        let new_name = registerName.to_owned() + "_evolution";
+        let _ = writeLine("Conjecture ".to_owned() + &*st.conjecture_name()
+                      + " | " + method + " best score yet : " + &*score.to_string()
+                      + " after " + &*elapsed.to_string()
+                      + "s, " + &*st.n_sommet.to_string()
+                      + " vertices, " + &*st.non_term_report() + "\n", new_name);
+                      }
+}
+
+// returns true if st is a counterexample, which is then reported (details follows the name of the method)
+// and saved as savedMatrix/<registerName>/conj<conjecture>
+pub fn saveCounterexample(st: &State, score: f64, start_time: &Instant, method: &str, details: &str, verbose: bool, registerName: &str) -> bool {
+    if !st.is_counterexample() {
+        return false
+    }
+    let elapsed = start_time.elapsed().as_secs_f64();
+    if verbose {
+        let _ = writeLine("Conjecture ".to_owned() + &*st.conjecture_name()
+                      + "\n        Counterexample found in " + &*elapsed.to_string()
+                      + "s: best score = " + &*score.to_string()
+                      + "\n        With " + method + details
+                      + ", " + &*st.n_sommet.to_string()
+                      + " vertices, " + &*st.connectivity_report() + ", " + &*st.non_term_report()
+                      + "\n\n", registerName.to_owned());
+                      }
+    println!("Conjecture {}\n   Counter-example found with {}{} after {}s ({}, {})\n\n", st.conjecture_name(), method, details, elapsed, st.connectivity_report(), st.non_term_report());
+
+    if let Err(e) = fs::create_dir_all(format!("savedMatrix/{}", registerName)) {
+        println!("error, cannot create savedMatrix/{}: {}", registerName, e);
+    }
+    graphToDot::adj_matrix_to_dot(st.adj_mat.clone(), &*format!("{}/conj{}", registerName, st.conjecture_name()));
+    let _ = saveMatrix::save_matrix(&*format!("{}/conj{}", registerName, st.conjecture_name()), st.adj_mat.clone());
+    true
+}
+
+// both of the above for a new best state, returns true if it is a counterexample
+pub fn recordBest(st: &State, score: f64, start_time: &Instant, method: &str, details: &str, verbose: bool, registerName: &str) -> bool {
+    logBest(st, score, start_time, method, verbose, registerName);
+    saveCounterexample(st, score, start_time, method, details, verbose, registerName)
+}
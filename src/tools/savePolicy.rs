@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufRead};
+use crate::models::policyEncoding::PolicyKey;
+use crate::tools::resultSaver::createFile;
+
+// one key per line, its encoding then its fields and the weight: "move, ind, from, to, kind, weight", "degrees, kind, d1, d2, weight", ...
+pub fn savePolicy(name : &str, policy : &HashMap<PolicyKey, f64>) -> std::io::Result<()> {
+    let mut file = createFile(&format!("savedMatrix/{}.txt", name))?;
+    for (key, w) in policy {
+        writeln!(file, "{}, {}", key.to_words(), w)?;
+    }
+    Ok(())
+}
+
+pub fn readPolicy(name : &str) -> HashMap<PolicyKey, f64> {
+    let mut policy = HashMap::new();
+    let file = match File::open(format!("savedMatrix/{}.txt", name)) {
+        Ok(f) => f,
+        Err(_) => {
+            println!("error, cannot read the policy {}", name);
+            return policy;
+        }
+    };
+
+    for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+        let words: Vec<&str> = line.split(", ").collect();
+        if let Some((w, key)) = words.split_last() {
+            if let (Some(key), Ok(w)) = (PolicyKey::parse(key), w.parse::<f64>()) {
+                policy.insert(key, w);
+            }
+        }
+    }
+    policy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::conjectures::conjectures_wagner_1::{Move, MoveKind};
+    use crate::models::policyEncoding::MoveType;
+
+    #[test]
+    fn policy_round_trip() {
+        let policy: HashMap<PolicyKey, f64> = [
+            (PolicyKey::Move(Move{ind: 3, from: 1, to: -1, kind: MoveKind::AddEdge}), 0.75),
+            (PolicyKey::Degrees(MoveKind::ToggleEdge, 2, usize::MAX), -1.5),
+            (PolicyKey::Type(MoveKind::RemoveVertex, MoveType::RemoveVertex), 1e-12),
+            (PolicyKey::Distance(MoveKind::RemoveEdge, 4), 0.0)
+        ].into_iter().collect();
+        // the folder of the policy is created on the way
+        let name = "policyRoundTrip/policy1";
+        savePolicy(name, &policy).unwrap();
+        let back = readPolicy(name);
+        let _ = std::fs::remove_dir_all("savedMatrix/policyRoundTrip");
+
+        assert_eq!(back, policy);
+        assert!(readPolicy(name).is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufRead};
+use crate::methods::GRAVE::transEntry;
+use crate::models::conjectures::conjectures_wagner_1::Move;
+use crate::models::policyEncoding::{move_to_words, move_from_words};
+use crate::tools::resultSaver::createFile;
+
+// every entry starts with "entry, allplayouts, m1; m2; ..." (its sequence of moves, "ind from to kind" each),
+// followed by its statistics "wins|playouts|winsAMAF|playoutsAMAF, ind, from, to, kind, value"
+pub fn saveTransTable(name : &str, table : &HashMap<Vec<Move>, transEntry>) -> std::io::Result<()> {
+    let mut file = createFile(&format!("savedMatrix/{}.txt", name))?;
+    for (seq, entry) in table {
+        let moves: Vec<String> = seq.iter().map(|m| move_to_words(m).replace(", ", " ")).collect();
+        writeln!(file, "entry, {}, {}", entry.allplayouts, moves.join("; "))?;
+        for (m, v) in &entry.wins {
+            writeln!(file, "wins, {}, {}", move_to_words(m), v)?;
+        }
+        for (m, v) in &entry.playouts {
+            writeln!(file, "playouts, {}, {}", move_to_words(m), v)?;
+        }
+        for (m, v) in &entry.winsAMAF {
+            writeln!(file, "winsAMAF, {}, {}", move_to_words(m), v)?;
+        }
+        for (m, v) in &entry.playoutsAMAF {
+            writeln!(file, "playoutsAMAF, {}, {}", move_to_words(m), v)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn readTransTable(name : &str) -> HashMap<Vec<Move>, transEntry> {
+    let mut table = HashMap::new();
+    let file = match File::open(format!("savedMatrix/{}.txt", name)) {
+        Ok(f) => f,
+        Err(_) => {
+            println!("error, cannot read the transposition table {}", name);
+            return table;
+        }
+    };
+
+    let mut current: Option<(Vec<Move>, transEntry)> = None;
+    for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+        let words: Vec<&str> = line.splitn(3, ", ").collect();
+        if words[0] == "entry" && words.len() == 3 {
+            if let Some((seq, entry)) = current.take() {
+                table.insert(seq, entry);
+            }
+            let seq: Option<Vec<Move>> = words[2].split("; ").filter(|m| !m.is_empty())
+                .map(|m| move_from_words(&m.split(' ').collect::<Vec<&str>>())).collect();
+            if let (Some(seq), Ok(allplayouts)) = (seq, words[1].parse()) {
+                let entry = transEntry{wins : HashMap::new(), playouts : HashMap::new(), winsAMAF : HashMap::new(), playoutsAMAF : HashMap::new(), allplayouts};
+                current = Some((seq, entry));
+            }
+            continue;
+        }
+
+        let words: Vec<&str> = line.split(", ").collect();
+        if words.len() != 6 {
+            continue;
+        }
+        if let (Some((_, entry)), Some(m)) = (current.as_mut(), move_from_words(&words[1..5])) {
+            match words[0] {
+                "wins" => { if let Ok(v) = words[5].parse() { entry.wins.insert(m, v); } },
+                "playouts" => { if let Ok(v) = words[5].parse() { entry.playouts.insert(m, v); } },
+                "winsAMAF" => { if let Ok(v) = words[5].parse() { entry.winsAMAF.insert(m, v); } },
+                "playoutsAMAF" => { if let Ok(v) = words[5].parse() { entry.playoutsAMAF.insert(m, v); } },
+                _ => {}
+            }
+        }
+    }
+    if let Some((seq, entry)) = current {
+        table.insert(seq, entry);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::conjectures::conjectures_wagner_1::MoveKind;
+
+    #[test]
+    fn transposition_table_round_trip() {
+        let a = Move{ind: 0, from: 0, to: -1, kind: MoveKind::AddEdge};
+        let b = Move{ind: 4, from: 2, to: 1, kind: MoveKind::RemoveVertex};
+        let root = transEntry{wins: [(a, 1.5)].into_iter().collect(), playouts: [(a, 2)].into_iter().collect(),
+                              winsAMAF: [(a, 0.5), (b, -3.0)].into_iter().collect(), playoutsAMAF: [(a, 1), (b, 4)].into_iter().collect(),
+                              allplayouts: 6};
+        let child = transEntry{wins: HashMap::new(), playouts: HashMap::new(), winsAMAF: HashMap::new(), playoutsAMAF: HashMap::new(), allplayouts: 1};
+        let mut table = HashMap::new();
+        table.insert(vec![], root);
+        table.insert(vec![a, b], child);
+
+        let name = "transTableRoundTrip";
+        saveTransTable(name, &table).unwrap();
+        let back = readTransTable(name);
+        let _ = std::fs::remove_file(format!("savedMatrix/{}.txt", name));
+
+        assert_eq!(back.len(), 2);
+        for (seq, entry) in &table {
+            let read = &back[seq];
+            assert_eq!(read.wins, entry.wins);
+            assert_eq!(read.playouts, entry.playouts);
+            assert_eq!(read.winsAMAF, entry.winsAMAF);
+            assert_eq!(read.playoutsAMAF, entry.playoutsAMAF);
+            assert_eq!(read.allplayouts, entry.allplayouts);
+        }
+        assert!(readTransTable(name).is_empty());
+    }
+}